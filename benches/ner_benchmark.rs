@@ -0,0 +1,68 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rust_bert::pipelines::ner::NERModel;
+use std::env;
+use tch::Device;
+
+static BATCH_SIZES: [usize; 3] = [1, 8, 32];
+static SEQUENCE_LENGTHS: [usize; 3] = [16, 64, 256];
+
+fn create_model() -> Option<NERModel> {
+    let model_dir = match env::var("NER_MODEL_DIR") {
+        Ok(model_dir) => model_dir,
+        Err(_) => {
+            eprintln!(
+                "Skipping NER benchmark: set the \"NER_MODEL_DIR\" environment variable to a local model directory"
+            );
+            return None;
+        }
+    };
+    match NERModel::from_dir(&model_dir, Device::Cpu) {
+        Ok(model) => Some(model),
+        Err(error) => {
+            eprintln!("Skipping NER benchmark: could not load the model from {model_dir}: {error}");
+            None
+        }
+    }
+}
+
+/// Builds an input of approximately `num_words` words by repeating a sentence containing entities
+fn create_input(num_words: usize) -> String {
+    let sentence = ["My", "name", "is", "Amy.", "I", "live", "in", "Paris."];
+    sentence
+        .iter()
+        .cycle()
+        .take(num_words)
+        .copied()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn bench_ner_predict(c: &mut Criterion) {
+    //    Set-up model
+    let model = match create_model() {
+        Some(model) => model,
+        None => return,
+    };
+
+    let mut group = c.benchmark_group("ner_predict");
+    for sequence_length in SEQUENCE_LENGTHS {
+        for batch_size in BATCH_SIZES {
+            //    Define input
+            let inputs = vec![create_input(sequence_length); batch_size];
+            group.throughput(Throughput::Elements(batch_size as u64));
+            group.bench_with_input(
+                BenchmarkId::new(format!("{sequence_length}_words"), batch_size),
+                &inputs,
+                |b, inputs| b.iter(|| model.predict(black_box(inputs.as_slice()))),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = bench_ner_predict
+}
+criterion_main!(benches);
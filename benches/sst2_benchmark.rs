@@ -28,15 +28,7 @@ fn sst2_forward_pass(iters: u64, model: &SentimentModel, sst2_data: &[String]) -
     for _i in 0..iters {
         let start = Instant::now();
         for batch in sst2_data.chunks(batch_size) {
-            output.push(
-                model.predict(
-                    batch
-                        .iter()
-                        .map(|v| v.as_str())
-                        .collect::<Vec<&str>>()
-                        .as_slice(),
-                ),
-            );
+            output.push(model.predict(batch).unwrap());
         }
         duration = duration.checked_add(start.elapsed()).unwrap();
     }
@@ -0,0 +1,49 @@
+// Copyright 2023 The rust-bert contributors
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rust_bert::pipelines::ner::NERModel;
+use tch::Device;
+
+/// Download a multilingual BERT (mBERT) model fine-tuned for NER:
+///   ```sh
+///   git lfs install
+///   git -C resources clone https://huggingface.co/Davlan/bert-base-multilingual-cased-ner-hrl
+///   ```
+/// The `model.safetensors` weights of the repository are loaded directly. For repositories only providing
+/// `pytorch_model.bin` weights, convert them with:
+///   ```sh
+///   python ./utils/convert_model.py resources/bert-base-multilingual-cased-ner-hrl/pytorch_model.bin
+///   ```
+fn main() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::from_dir(
+        "resources/bert-base-multilingual-cased-ner-hrl",
+        Device::cuda_if_available(),
+    )?;
+
+    //    Define input, including scripts written without spaces between words
+    let input = [
+        "私の名前はエイミーです。東京に住んでいます。",
+        "我叫艾米，我住在北京。",
+        "Меня зовут Амели. Я живу в Москве.",
+        "My name is Amy. I live in Paris.",
+    ];
+
+    //    Run model
+    let output = ner_model.predict(&input);
+    for entities in output {
+        for entity in entities {
+            println!("{entity:?}");
+        }
+    }
+
+    Ok(())
+}
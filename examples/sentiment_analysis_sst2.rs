@@ -52,15 +52,7 @@ fn main() -> anyhow::Result<()> {
     let batch_size = 64;
     let mut output = vec![];
     for batch in inputs.chunks(batch_size) {
-        output.push(
-            sentiment_classifier.predict(
-                batch
-                    .iter()
-                    .map(|v| v.as_str())
-                    .collect::<Vec<&str>>()
-                    .as_slice(),
-            ),
-        );
+        output.push(sentiment_classifier.predict(batch)?);
     }
     let mut flat_outputs = vec![];
     for batch_output in output.iter_mut() {
@@ -1098,6 +1098,161 @@ impl BertForTokenClassification {
     }
 }
 
+/// # BERT for span classification (e.g. nested NER)
+/// Span-level classifier predicting a label for each candidate span of tokens provided. Unlike token classification, the spans
+/// may overlap, allowing the representation of nested entities. Each span is represented by the concatenation of the hidden states
+/// of its first and last tokens.
+/// It is made of the following blocks:
+/// - `bert`: Base BertModel
+/// - `span_classifier`: Linear layer for span classification
+pub struct BertForSpanClassification {
+    bert: BertModel<BertEmbeddings>,
+    dropout: Dropout,
+    span_classifier: nn::Linear,
+}
+
+impl BertForSpanClassification {
+    /// Build a new `BertForSpanClassification`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the BertForSpanClassification model
+    /// * `config` - `BertConfig` object defining the model architecture, number of output labels and label mapping
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::bert::{BertConfig, BertForSpanClassification};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = BertConfig::from_file(config_path);
+    /// let bert = BertForSpanClassification::new(&p.root(), &config).unwrap();
+    /// ```
+    pub fn new<'p, P>(p: P, config: &BertConfig) -> Result<BertForSpanClassification, RustBertError>
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let bert = BertModel::new(p / "bert", config);
+        let dropout = Dropout::new(config.hidden_dropout_prob);
+        let num_labels = config
+            .id2label
+            .as_ref()
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "num_labels not provided in configuration".to_string(),
+                )
+            })?
+            .len() as i64;
+        let span_classifier = nn::linear(
+            p / "span_classifier",
+            2 * config.hidden_size,
+            num_labels,
+            Default::default(),
+        );
+
+        Ok(BertForSpanClassification {
+            bert,
+            dropout,
+            span_classifier,
+        })
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Input tensor of shape (*batch size*, *sequence_length*)
+    /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `token_type_ids` -Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
+    /// * `span_starts` - Position of the first token of each candidate span, of shape (*batch size*, *num_spans*)
+    /// * `span_ends` - Position of the last token (inclusive) of each candidate span, of shape (*batch size*, *num_spans*)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<BertSpanClassificationOutput, RustBertError>` containing, or an error if the input or span tensors
+    /// have an invalid shape:
+    ///   - `logits` - `Tensor` of shape (*batch size*, *num_spans*, *num_labels*) containing the logits for each of the candidate spans and classes
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::bert::{BertForSpanClassification, BertConfig};
+    /// # use tch::{nn, Device, Tensor, no_grad};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::Int64;
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = BertConfig::from_file(config_path);
+    /// # let bert_model = BertForSpanClassification::new(&vs.root(), &config).unwrap();
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let span_starts = Tensor::of_slice(&[1i64, 1, 2]).expand(&[batch_size, 3], true);
+    /// let span_ends = Tensor::of_slice(&[1i64, 2, 2]).expand(&[batch_size, 3], true);
+    ///
+    /// let model_output = no_grad(|| {
+    ///     bert_model.forward_t(&input_tensor, None, None, &span_starts, &span_ends, false)
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: &Tensor,
+        mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        span_starts: &Tensor,
+        span_ends: &Tensor,
+        train: bool,
+    ) -> Result<BertSpanClassificationOutput, RustBertError> {
+        let base_model_output = self.bert.forward_t(
+            Some(input_ids),
+            mask,
+            token_type_ids,
+            None,
+            None,
+            None,
+            None,
+            train,
+        )?;
+
+        let hidden_state = base_model_output.hidden_state.apply_t(&self.dropout, train);
+        let (batch_size, num_spans) = span_starts.size2()?;
+        let hidden_size = hidden_state.size()[2];
+        let gather_span_token = |positions: &Tensor| {
+            hidden_state.gather(
+                1,
+                &positions
+                    .unsqueeze(-1)
+                    .expand(&[batch_size, num_spans, hidden_size], true),
+                false,
+            )
+        };
+        let logits = Tensor::cat(
+            &[gather_span_token(span_starts), gather_span_token(span_ends)],
+            -1,
+        )
+        .apply(&self.span_classifier);
+        Ok(BertSpanClassificationOutput {
+            logits,
+            all_hidden_states: base_model_output.all_hidden_states,
+            all_attentions: base_model_output.all_attentions,
+        })
+    }
+}
+
 /// # BERT for question answering
 /// Extractive question-answering model based on a BERT language model. Identifies the segment of a context that answers a provided question.
 /// Please note that a significant amount of pre- and post-processing is required to perform end-to-end question answering.
@@ -1284,6 +1439,16 @@ pub struct BertTokenClassificationOutput {
     pub all_attentions: Option<Vec<Tensor>>,
 }
 
+/// Container for the BERT span classification model output.
+pub struct BertSpanClassificationOutput {
+    /// Logits for each candidate span for each target class
+    pub logits: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
 /// Container for the BERT question answering model output.
 pub struct BertQuestionAnsweringOutput {
     /// Logits for the start position for token of each input sequence
@@ -7,6 +7,7 @@
 //! - Question answering: `bert_model::BertForQuestionAnswering`
 //! - Sequence classification: `bert_model::BertForSequenceClassification`
 //! - Token classification (e.g. NER, POS tagging): `bert_model::BertForTokenClassification`
+//! - Span classification (e.g. nested NER): `bert_model::BertForSpanClassification`
 //!
 //! # Model set-up and pre-trained weights loading
 //!
@@ -60,8 +61,9 @@ pub(crate) mod encoder;
 pub use bert_model::{
     BertConfig, BertConfigResources, BertForMaskedLM, BertForMultipleChoice,
     BertForQuestionAnswering, BertForSentenceEmbeddings, BertForSequenceClassification,
-    BertForTokenClassification, BertMaskedLMOutput, BertModel, BertModelOutput, BertModelResources,
-    BertQuestionAnsweringOutput, BertSequenceClassificationOutput, BertTokenClassificationOutput,
+    BertForSpanClassification, BertForTokenClassification, BertMaskedLMOutput, BertModel,
+    BertModelOutput, BertModelResources, BertQuestionAnsweringOutput,
+    BertSequenceClassificationOutput, BertSpanClassificationOutput, BertTokenClassificationOutput,
     BertVocabResources,
 };
 pub use embeddings::{BertEmbedding, BertEmbeddings};
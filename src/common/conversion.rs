@@ -0,0 +1,275 @@
+// Copyright 2023 The rust-bert contributors
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Weights conversion without Python
+//! Converts model weights stored in the [safetensors](https://huggingface.co/docs/safetensors) format (`model.safetensors`
+//! files of the Hugging Face model hub) to the `.ot` format expected by `tch`. Tensor names are kept as-is, except for the
+//! legacy `gamma` and `beta` layer normalization parameter names that are renamed to `weight` and `bias`, consistently
+//! with the `./utils/convert_model.py` script.
+//!
+//! Safetensors weights can also be loaded directly into a `VarStore` with `load_safetensors`. The pipelines relying on
+//! resources load them automatically when the model resource points to a local file with the `.safetensors` extension.
+//!
+//! PyTorch `.bin` checkpoints are Python pickles and can not be read without Python: they still require the conversion script.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::conversion::convert_safetensors;
+//!
+//! convert_safetensors("path/to/model.safetensors", "path/to/rust_model.ot")?;
+//! # Ok(())
+//! # }
+//! ```
+//! The conversion is also available from the command line with the `convert-tensor` binary:
+//! `cargo run --bin=convert-tensor -- path/to/model.safetensors path/to/rust_model.ot`
+
+use crate::common::resources::weight_mismatch;
+use crate::RustBertError;
+use safetensors::{Dtype, SafeTensors};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use tch::nn::VarStore;
+use tch::{no_grad, Kind, Tensor};
+
+/// Reads the tensors of a safetensors file
+///
+/// # Arguments
+///
+/// * `path` - Path to the `.safetensors` file
+///
+/// # Returns
+///
+/// * `Vec<(String, Tensor)>` named tensors (on the CPU) read from the file, with names converted to the `tch` convention
+pub fn read_safetensors<P: AsRef<Path>>(path: P) -> Result<Vec<(String, Tensor)>, RustBertError> {
+    let buffer = fs::read(path)?;
+    read_safetensors_from_buffer(&buffer)
+}
+
+/// Reads the tensors of a safetensors file loaded in memory
+///
+/// # Arguments
+///
+/// * `buffer` - Content of the `.safetensors` file
+///
+/// # Returns
+///
+/// * `Vec<(String, Tensor)>` named tensors (on the CPU) read from the buffer, with names converted to the `tch` convention
+pub fn read_safetensors_from_buffer(buffer: &[u8]) -> Result<Vec<(String, Tensor)>, RustBertError> {
+    let safetensors = SafeTensors::deserialize(buffer).map_err(|error| {
+        RustBertError::IOError(format!("Invalid safetensors content: {error:?}"))
+    })?;
+    safetensors
+        .tensors()
+        .into_iter()
+        .map(|(name, view)| {
+            let kind = dtype_to_kind(view.dtype())?;
+            let shape = view
+                .shape()
+                .iter()
+                .map(|dim| *dim as i64)
+                .collect::<Vec<i64>>();
+            let tensor = Tensor::of_data_size(view.data(), &shape, kind);
+            Ok((convert_tensor_name(&name), tensor))
+        })
+        .collect()
+}
+
+/// Converts a safetensors file to the `.ot` format that can be loaded in a `VarStore`
+///
+/// # Arguments
+///
+/// * `source` - Path to the `.safetensors` file to convert
+/// * `destination` - Path to the `.ot` file to create
+pub fn convert_safetensors<P: AsRef<Path>, Q: AsRef<Path>>(
+    source: P,
+    destination: Q,
+) -> Result<(), RustBertError> {
+    let tensors = read_safetensors(source)?;
+    Tensor::save_multi(&tensors, destination)?;
+    Ok(())
+}
+
+/// Loads the weights of a safetensors file into a `VarStore`, without conversion to the `.ot` format.
+/// If tensors expected by the model are absent from the file, a `RustBertError::WeightMismatch`
+/// listing the missing and unexpected tensor names is returned. A tensor of the file with a shape different
+/// from the model variable it is loaded into results in an error naming the tensor.
+///
+/// # Arguments
+///
+/// * `path` - Path to the `.safetensors` file
+/// * `var_store` - `VarStore` of the model, the weights are copied to the device of its variables
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use rust_bert::bert::{BertConfig, BertForTokenClassification};
+/// use rust_bert::conversion::load_safetensors;
+/// use rust_bert::Config;
+/// use tch::{nn, Device};
+///
+/// let config = BertConfig::from_file("path/to/config.json");
+/// let mut var_store = nn::VarStore::new(Device::cuda_if_available());
+/// let model = BertForTokenClassification::new(var_store.root(), &config);
+/// load_safetensors("path/to/model.safetensors", &mut var_store)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn load_safetensors<P: AsRef<Path>>(
+    path: P,
+    var_store: &mut VarStore,
+) -> Result<(), RustBertError> {
+    let tensors = read_safetensors(path)?;
+    copy_to_var_store(tensors, var_store)
+}
+
+/// Loads the weights of a safetensors file held in memory into a `VarStore`.
+///
+/// # Arguments
+///
+/// * `buffer` - Content of the `.safetensors` file
+/// * `var_store` - `VarStore` of the model, the weights are copied to the device of its variables
+pub fn load_safetensors_from_buffer(
+    buffer: &[u8],
+    var_store: &mut VarStore,
+) -> Result<(), RustBertError> {
+    let tensors = read_safetensors_from_buffer(buffer)?;
+    copy_to_var_store(tensors, var_store)
+}
+
+fn copy_to_var_store(
+    tensors: Vec<(String, Tensor)>,
+    var_store: &mut VarStore,
+) -> Result<(), RustBertError> {
+    let file_tensors = tensors.into_iter().collect::<HashMap<String, Tensor>>();
+    let mut variables = var_store.variables();
+    if variables
+        .keys()
+        .any(|name| !file_tensors.contains_key(name))
+    {
+        let file_names = file_tensors.into_keys().collect::<HashSet<String>>();
+        return Err(weight_mismatch(var_store, &file_names));
+    }
+    no_grad(|| {
+        for (name, variable) in variables.iter_mut() {
+            let file_tensor = &file_tensors[name];
+            if variable.size() != file_tensor.size() {
+                return Err(RustBertError::TchError(format!(
+                    "Incompatible shape for tensor {name}: the model expects {:?}, the weights contain {:?}",
+                    variable.size(),
+                    file_tensor.size()
+                )));
+            }
+            variable.f_copy_(file_tensor).map_err(|error| {
+                RustBertError::TchError(format!("Could not load tensor {name}: {error}"))
+            })?;
+        }
+        Ok(())
+    })
+}
+
+fn convert_tensor_name(name: &str) -> String {
+    name.replace("gamma", "weight").replace("beta", "bias")
+}
+
+fn dtype_to_kind(dtype: Dtype) -> Result<Kind, RustBertError> {
+    Ok(match dtype {
+        Dtype::BOOL => Kind::Bool,
+        Dtype::U8 => Kind::Uint8,
+        Dtype::I8 => Kind::Int8,
+        Dtype::I16 => Kind::Int16,
+        Dtype::I32 => Kind::Int,
+        Dtype::I64 => Kind::Int64,
+        Dtype::F16 => Kind::Half,
+        Dtype::BF16 => Kind::BFloat16,
+        Dtype::F32 => Kind::Float,
+        Dtype::F64 => Kind::Double,
+        _ => {
+            return Err(RustBertError::ValueError(format!(
+                "Unsupported safetensors data type: {dtype:?}"
+            )));
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use safetensors::tensor::TensorView;
+
+    #[test]
+    fn safetensors_buffer_to_tensors() -> anyhow::Result<()> {
+        //    Given
+        let values = [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let data = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<u8>>();
+        let view = TensorView::new(Dtype::F32, vec![2, 3], &data).unwrap();
+        let buffer =
+            safetensors::serialize(vec![("encoder.LayerNorm.gamma", view)], &None).unwrap();
+
+        //    When
+        let tensors = read_safetensors_from_buffer(&buffer)?;
+
+        //    Then
+        assert_eq!(tensors.len(), 1);
+        let (name, tensor) = &tensors[0];
+        assert_eq!(name, "encoder.LayerNorm.weight");
+        assert_eq!(tensor.size(), vec![2, 3]);
+        assert_eq!(tensor.kind(), Kind::Float);
+        assert_eq!(tensor.double_value(&[1, 2]), 6.0);
+        Ok(())
+    }
+
+    #[test]
+    fn safetensors_buffer_to_var_store() -> anyhow::Result<()> {
+        //    Given
+        let mut var_store = VarStore::new(tch::Device::Cpu);
+        let weight = (var_store.root() / "classifier").zeros("weight", &[2]);
+        let data = [0.5f32, -1.5]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<u8>>();
+        let view = TensorView::new(Dtype::F32, vec![2], &data).unwrap();
+        let buffer = safetensors::serialize(vec![("classifier.weight", view)], &None).unwrap();
+        let view = TensorView::new(Dtype::F32, vec![2], &data).unwrap();
+        let unexpected_buffer =
+            safetensors::serialize(vec![("pooler.weight", view)], &None).unwrap();
+
+        //    When
+        load_safetensors_from_buffer(&buffer, &mut var_store)?;
+        let mismatch = load_safetensors_from_buffer(&unexpected_buffer, &mut var_store);
+
+        //    Then
+        assert_eq!(weight.double_value(&[0]), 0.5);
+        assert_eq!(weight.double_value(&[1]), -1.5);
+        let view = TensorView::new(Dtype::F32, vec![1, 2], &data).unwrap();
+        let reshaped_buffer =
+            safetensors::serialize(vec![("classifier.weight", view)], &None).unwrap();
+        match load_safetensors_from_buffer(&reshaped_buffer, &mut var_store) {
+            Err(RustBertError::TchError(message)) => assert!(message.contains("classifier.weight")),
+            _ => panic!("expected a shape error"),
+        }
+        match mismatch {
+            Err(RustBertError::WeightMismatch {
+                missing,
+                unexpected,
+            }) => {
+                assert_eq!(missing, vec!["classifier.weight".to_string()]);
+                assert_eq!(unexpected, vec!["pooler.weight".to_string()]);
+            }
+            _ => panic!("expected a weight mismatch error"),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+use log::debug;
+use tch::{Cuda, Device};
+
+/// Returns true if CUDA is available at runtime, i.e. if `Device::cuda_if_available()` selects a GPU.
+/// This requires both a CUDA-enabled build of libtorch and at least one visible CUDA device.
+///
+/// # Example
+///
+/// ```no_run
+/// let backend = if rust_bert::cuda_is_available() {
+///     format!("CUDA ({} devices)", rust_bert::cuda_device_count())
+/// } else {
+///     "CPU".to_string()
+/// };
+/// println!("Running on {backend}");
+/// ```
+pub fn cuda_is_available() -> bool {
+    Cuda::is_available()
+}
+
+/// Returns the number of CUDA devices visible to the process (0 if CUDA is not available)
+pub fn cuda_device_count() -> usize {
+    Cuda::device_count().max(0) as usize
+}
+
+/// Selects a device by index, distributing indices round-robin over the visible CUDA devices.
+/// Falls back to `Device::Cpu` if no CUDA device is available.
+///
+/// This can be used to spread several models over the available GPUs, for example by selecting
+/// `select_device(model_index)` for each model. Note that the selection does not take the current
+/// load of the devices into account.
+///
+/// # Arguments
+///
+/// * `index` - `usize` index of the device to select (wrapped around the number of CUDA devices)
+///
+/// # Example
+///
+/// ```no_run
+/// use rust_bert::select_device;
+///
+/// let devices = (0..4).map(select_device).collect::<Vec<_>>();
+/// ```
+pub fn select_device(index: usize) -> Device {
+    let device = match cuda_device_count() {
+        0 => Device::Cpu,
+        device_count => Device::Cuda(index % device_count),
+    };
+    debug!("Selected device {:?} for index {}", device, index);
+    device
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn select_device_round_robin() {
+        let device_count = cuda_device_count();
+        for index in 0..4 {
+            let expected_device = if device_count == 0 {
+                Device::Cpu
+            } else {
+                Device::Cuda(index % device_count)
+            };
+            assert_eq!(select_device(index), expected_device);
+        }
+    }
+
+    #[test]
+    fn cuda_availability_matches_device_count() {
+        assert_eq!(cuda_is_available(), cuda_device_count() > 0);
+        assert_eq!(
+            cuda_is_available(),
+            Device::cuda_if_available() != Device::Cpu
+        );
+    }
+}
@@ -3,6 +3,10 @@ use tch::TchError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
+/// # Error type returned by the models and pipelines of the crate
+/// Implements `std::error::Error` and is `Send + Sync + 'static`: it converts with the `?` operator into error types
+/// with a blanket conversion from standard errors, such as `anyhow::Error` or `Box<dyn std::error::Error + Send + Sync>`.
+/// No feature flag is needed.
 pub enum RustBertError {
     #[cfg(feature = "remote")]
     #[error("Endpoint not available error: {0}")]
@@ -22,6 +26,23 @@ pub enum RustBertError {
 
     #[error("Value error: {0}")]
     ValueError(String),
+
+    #[cfg(feature = "onnx")]
+    #[error("ONNX Runtime error: {0}")]
+    ONNXError(String),
+
+    #[error("Prediction cancelled")]
+    Cancelled,
+
+    #[error(
+        "Weight mismatch error: missing tensors {missing:?}, unexpected tensors {unexpected:?}"
+    )]
+    WeightMismatch {
+        /// Tensors expected by the model but absent from the weights file
+        missing: Vec<String>,
+        /// Tensors of the weights file not used by the model
+        unexpected: Vec<String>,
+    },
 }
 
 impl From<std::io::Error> for RustBertError {
@@ -41,3 +62,38 @@ impl From<TchError> for RustBertError {
         RustBertError::TchError(error.to_string())
     }
 }
+
+#[cfg(feature = "onnx")]
+impl From<ort::OrtError> for RustBertError {
+    fn from(error: ort::OrtError) -> Self {
+        RustBertError::ONNXError(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_standard_error<E: std::error::Error + Send + Sync + 'static>() {}
+
+    #[test]
+    fn converts_into_standard_error_types() {
+        assert_standard_error::<RustBertError>();
+
+        let to_anyhow = || -> anyhow::Result<()> {
+            Err(RustBertError::ValueError("invalid value".to_string()))?;
+            Ok(())
+        };
+        let error = to_anyhow().unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<RustBertError>(),
+            Some(RustBertError::ValueError(_))
+        ));
+
+        let to_boxed = || -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            Err(RustBertError::Cancelled)?;
+            Ok(())
+        };
+        assert_eq!(to_boxed().unwrap_err().to_string(), "Prediction cancelled");
+    }
+}
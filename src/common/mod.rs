@@ -1,5 +1,7 @@
 pub(crate) mod activations;
 pub mod config;
+pub mod conversion;
+pub mod device;
 pub(crate) mod dropout;
 pub(crate) mod embeddings;
 pub mod error;
@@ -10,3 +12,4 @@ pub(crate) mod summary;
 
 pub use activations::Activation;
 pub use config::Config;
+pub use device::{cuda_device_count, cuda_is_available, select_device};
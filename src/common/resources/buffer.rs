@@ -0,0 +1,55 @@
+use crate::common::error::RustBertError;
+use crate::resources::{Resource, ResourceProvider};
+use std::path::PathBuf;
+
+/// # In-memory resource
+///
+/// Holds the content of a resource (typically model weights) in memory, for example when the
+/// weights are embedded in the binary or received over the network, avoiding the need to write
+/// them to a temporary file. Only weights can currently be loaded from a `BufferResource`:
+/// configuration and vocabulary files are still read from a path.
+#[derive(PartialEq, Eq, Clone)]
+pub struct BufferResource {
+    /// Raw content of the resource
+    pub data: Vec<u8>,
+}
+
+impl ResourceProvider for BufferResource {
+    /// A `BufferResource` is not backed by a file: this always returns an error.
+    /// Use `get_resource` to access the in-memory content.
+    fn get_local_path(&self) -> Result<PathBuf, RustBertError> {
+        Err(RustBertError::ValueError(
+            "BufferResource does not provide a local path, use get_resource instead".to_string(),
+        ))
+    }
+
+    /// Gets the in-memory content of the resource.
+    ///
+    /// # Returns
+    ///
+    /// * `Resource::Buffer` referencing the resource content
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::resources::{BufferResource, ResourceProvider};
+    /// let weights = std::fs::read("path/to/rust_model.ot").unwrap();
+    /// let weights_resource = BufferResource { data: weights };
+    /// let weights = weights_resource.get_resource();
+    /// ```
+    fn get_resource(&self) -> Result<Resource, RustBertError> {
+        Ok(Resource::Buffer(&self.data))
+    }
+}
+
+impl From<Vec<u8>> for BufferResource {
+    fn from(data: Vec<u8>) -> Self {
+        Self { data }
+    }
+}
+
+impl From<Vec<u8>> for Box<dyn ResourceProvider + Send> {
+    fn from(data: Vec<u8>) -> Self {
+        Box::new(BufferResource { data })
+    }
+}
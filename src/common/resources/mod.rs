@@ -8,20 +8,37 @@
 //! - (optional) merges files for BPE-based tokenizers
 //!
 //! These are expected in the pipelines configurations or are used as utilities to reference to the
-//! resource location. Two types of resources are pre-defined:
+//! resource location. Three types of resources are pre-defined:
 //! - LocalResource: points to a local file
 //! - RemoteResource: points to a remote file via a URL
+//! - BufferResource: holds the resource content in memory
 //!
 //! For both types of resources, the local location of the file can be retrieved using
 //! `get_local_path`, allowing to reference the resource file location regardless if it is a remote
 //! or local resource. Default implementations for a number of `RemoteResources` are available as
-//! pre-trained models in each model module.
+//! pre-trained models in each model module. Model weights can additionally be read from memory
+//! using `get_resource`, which is how `BufferResource` weights are loaded.
 
+mod buffer;
 mod local;
 
+use crate::common::conversion::{load_safetensors, read_safetensors};
 use crate::common::error::RustBertError;
+pub use buffer::BufferResource;
 pub use local::LocalResource;
+use std::collections::HashSet;
+use std::io::Cursor;
 use std::path::PathBuf;
+use tch::nn::VarStore;
+use tch::{TchError, Tensor};
+
+/// # Content of a resource, either as a local file or as an in-memory buffer
+pub enum Resource<'a> {
+    /// Path to a local file
+    PathBuf(PathBuf),
+    /// In-memory content
+    Buffer(&'a [u8]),
+}
 
 /// # Resource Trait that can provide the location of the model, configuration or vocabulary resources
 pub trait ResourceProvider {
@@ -42,6 +59,109 @@ pub trait ResourceProvider {
     /// let config_path = config_resource.get_local_path();
     /// ```
     fn get_local_path(&self) -> Result<PathBuf, RustBertError>;
+
+    /// Provides access to the resource, either as a local path or as an in-memory buffer.
+    /// Defaults to the local path returned by `get_local_path`.
+    ///
+    /// # Returns
+    ///
+    /// * `Resource` pointing to the resource file or holding its content
+    fn get_resource(&self) -> Result<Resource, RustBertError> {
+        Ok(Resource::PathBuf(self.get_local_path()?))
+    }
+}
+
+/// Loads the weights of a resource into a `VarStore`, from a local file or from memory.
+/// Local files with a `.safetensors` extension are read in the safetensors format, other weights in the `.ot` format.
+/// If tensors expected by the model are absent from the weights, a `RustBertError::WeightMismatch`
+/// listing the missing and unexpected tensor names is returned.
+pub(crate) fn load_weights(
+    resource: &(impl ResourceProvider + ?Sized),
+    var_store: &mut VarStore,
+) -> Result<(), RustBertError> {
+    match resource.get_resource()? {
+        Resource::PathBuf(path)
+            if path.extension().and_then(|extension| extension.to_str()) == Some("safetensors") =>
+        {
+            load_safetensors(&path, var_store)
+        }
+        Resource::PathBuf(path) => var_store
+            .load(&path)
+            .map_err(|err| weight_mismatch_error(err, var_store, || Tensor::load_multi(&path))),
+        Resource::Buffer(buffer) => {
+            var_store
+                .load_from_stream(Cursor::new(buffer))
+                .map_err(|err| {
+                    weight_mismatch_error(err, var_store, || {
+                        Tensor::load_multi_from_stream(Cursor::new(buffer))
+                    })
+                })
+        }
+    }
+}
+
+/// Returns the shape of a tensor of the weights of a resource, or `None` if the weights can not be read or
+/// do not contain a tensor with this name. Reads all the weights: intended for error reporting only.
+pub(crate) fn weight_shape(
+    resource: &(impl ResourceProvider + ?Sized),
+    name: &str,
+) -> Option<Vec<i64>> {
+    let tensors = match resource.get_resource().ok()? {
+        Resource::PathBuf(path)
+            if path.extension().and_then(|extension| extension.to_str()) == Some("safetensors") =>
+        {
+            read_safetensors(&path).ok()?
+        }
+        Resource::PathBuf(path) => Tensor::load_multi(&path).ok()?,
+        Resource::Buffer(buffer) => Tensor::load_multi_from_stream(Cursor::new(buffer)).ok()?,
+    };
+    tensors
+        .into_iter()
+        .find(|(tensor_name, _)| tensor_name == name)
+        .map(|(_, tensor)| tensor.size())
+}
+
+fn weight_mismatch_error<F>(
+    error: TchError,
+    var_store: &VarStore,
+    load_file_tensors: F,
+) -> RustBertError
+where
+    F: FnOnce() -> Result<Vec<(String, Tensor)>, TchError>,
+{
+    if !matches!(error, TchError::TensorNameNotFound(..)) {
+        return error.into();
+    }
+    let file_names = match load_file_tensors() {
+        Ok(tensors) => tensors
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<HashSet<String>>(),
+        Err(_) => return error.into(),
+    };
+    weight_mismatch(var_store, &file_names)
+}
+
+/// Lists the tensors of the `VarStore` absent from `file_names`, and the names of `file_names` not used by the model
+pub(crate) fn weight_mismatch(var_store: &VarStore, file_names: &HashSet<String>) -> RustBertError {
+    let model_names = var_store
+        .variables()
+        .into_keys()
+        .collect::<HashSet<String>>();
+    let mut missing = model_names
+        .difference(file_names)
+        .cloned()
+        .collect::<Vec<String>>();
+    let mut unexpected = file_names
+        .difference(&model_names)
+        .cloned()
+        .collect::<Vec<String>>();
+    missing.sort();
+    unexpected.sort();
+    RustBertError::WeightMismatch {
+        missing,
+        unexpected,
+    }
 }
 
 #[cfg(feature = "remote")]
@@ -3,7 +3,10 @@ use crate::common::error::RustBertError;
 use cached_path::{Cache, Options, ProgressBar};
 use dirs::cache_dir;
 use lazy_static::lazy_static;
-use std::path::PathBuf;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
 
 /// # Remote resource that will be downloaded and cached locally on demand
 #[derive(PartialEq, Eq, Clone)]
@@ -12,6 +15,9 @@ pub struct RemoteResource {
     pub url: String,
     /// Local subdirectory of the cache root where this resource is saved
     pub cache_subdir: String,
+    /// Expected SHA256 checksum (hexadecimal) of the resource. If set, the cached file is verified when the
+    /// resource is accessed and downloaded again if it does not match (e.g. truncated or corrupted downloads).
+    pub sha256: Option<String>,
 }
 
 impl RemoteResource {
@@ -37,6 +43,7 @@ impl RemoteResource {
         RemoteResource {
             url: url.to_string(),
             cache_subdir: cache_subdir.to_string(),
+            sha256: None,
         }
     }
 
@@ -64,10 +71,46 @@ impl RemoteResource {
     pub fn from_pretrained(name_url_tuple: (&str, &str)) -> RemoteResource {
         let cache_subdir = name_url_tuple.0.to_string();
         let url = name_url_tuple.1.to_string();
-        RemoteResource { url, cache_subdir }
+        RemoteResource {
+            url,
+            cache_subdir,
+            sha256: None,
+        }
+    }
+
+    /// Sets the expected SHA256 checksum of the resource. The cached file is verified against it when the
+    /// resource is accessed, and downloaded again on mismatch. An error is returned if the downloaded file
+    /// still does not match.
+    ///
+    /// # Arguments
+    ///
+    /// * `sha256` - `&str` Expected SHA256 checksum of the resource, as a hexadecimal string
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::resources::RemoteResource;
+    /// let config_resource = RemoteResource::new("http://config_json_location", "configs")
+    ///     .with_sha256("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    /// ```
+    pub fn with_sha256(mut self, sha256: &str) -> RemoteResource {
+        self.sha256 = Some(sha256.to_lowercase());
+        self
     }
 }
 
+/// Computes the SHA256 checksum of a file, as a lowercase hexadecimal string
+fn file_sha256(path: &Path) -> Result<String, RustBertError> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
 impl ResourceProvider for RemoteResource {
     /// Gets the local path for a remote resource.
     ///
@@ -91,7 +134,26 @@ impl ResourceProvider for RemoteResource {
     fn get_local_path(&self) -> Result<PathBuf, RustBertError> {
         let cached_path = CACHE
             .cached_path_with_options(&self.url, &Options::default().subdir(&self.cache_subdir))?;
-        Ok(cached_path)
+        let expected_sha256 = match &self.sha256 {
+            Some(expected_sha256) => expected_sha256,
+            None => return Ok(cached_path),
+        };
+        if &file_sha256(&cached_path)? == expected_sha256 {
+            return Ok(cached_path);
+        }
+        let cached_path = CACHE.cached_path_with_options(
+            &self.url,
+            &Options::default().subdir(&self.cache_subdir).force(),
+        )?;
+        let sha256 = file_sha256(&cached_path)?;
+        if &sha256 == expected_sha256 {
+            Ok(cached_path)
+        } else {
+            Err(RustBertError::IOError(format!(
+                "Checksum mismatch for {}: expected SHA256 {expected_sha256}, got {sha256}",
+                self.url
+            )))
+        }
     }
 }
 
@@ -117,3 +179,21 @@ fn _get_cache_directory() -> PathBuf {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn file_sha256_matches_known_digest() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let path = directory.path().join("resource.txt");
+        std::fs::write(&path, "abc")?;
+
+        assert_eq!(
+            file_sha256(&path)?,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        Ok(())
+    }
+}
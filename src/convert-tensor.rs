@@ -12,6 +12,7 @@
 
 extern crate tch;
 
+use rust_bert::conversion::convert_safetensors;
 use rust_bert::RustBertError;
 
 pub fn main() -> Result<(), RustBertError> {
@@ -19,14 +20,18 @@ pub fn main() -> Result<(), RustBertError> {
     assert_eq!(
         args.len(),
         3,
-        "usage: {} source.npz destination.ot",
+        "usage: {} source.npz|source.safetensors destination.ot",
         args[0].as_str()
     );
 
     let source_file = &args[1];
     let destination_file = &args[2];
-    let tensors = tch::Tensor::read_npz(source_file)?;
-    tch::Tensor::save_multi(&tensors, destination_file)?;
+    if source_file.ends_with(".safetensors") {
+        convert_safetensors(source_file, destination_file)?;
+    } else {
+        let tensors = tch::Tensor::read_npz(source_file)?;
+        tch::Tensor::save_multi(&tensors, destination_file)?;
+    }
 
     Ok(())
 }
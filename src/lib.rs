@@ -109,6 +109,14 @@
 //! The CPU version of libtorch will be downloaded by default. To download a CUDA version, please set the environment variable `TORCH_CUDA_VERSION` to `cu118`.
 //! Note that the libtorch library is large (order of several GBs for the CUDA-enabled version) and the first build may therefore take several minutes to complete.
 //!
+//! ### CPU-only environments
+//!
+//! CUDA linkage is determined by the libtorch distribution used at build time rather than by a cargo feature: the `cpu-only`
+//! feature documents the intent of CPU-only builds but sets no `tch` feature, and no other feature flag is required. Use the CPU version of libtorch (downloaded by default by the automatic installation
+//! when `TORCH_CUDA_VERSION` is not set, or the `cpu` variant of the manual download) and no CUDA runtime is needed.
+//! `Device::cuda_if_available()` (used by the default pipeline configurations) returns `Device::Cpu` when no GPU is available,
+//! and the device can also be set explicitly, for example `NERModel::from_dir(model_dir, Device::Cpu)`.
+//!
 //! # Ready-to-use pipelines
 //!
 //! Based on Hugging Face's pipelines, ready to use end-to-end NLP pipelines are available as part of this crate. More information on these can be found in the [`pipelines` module](./pipelines/index.html)
@@ -419,7 +427,7 @@
 //!     "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
 //!     "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
 //! ];
-//! let output = sentiment_model.predict(&input);
+//! let output = sentiment_model.predict(&input)?;
 //! # Ok(())
 //! # }
 //! ```
@@ -478,12 +486,16 @@
 //!             score: 0.9986,
 //!             label: String::from("I-PER"),
 //!             offset: Offset { begin: 11, end: 14 },
+//!             token_start: 4,
+//!             token_end: 5,
 //!         },
 //!         Entity {
 //!             word: String::from("Paris"),
 //!             score: 0.9985,
 //!             label: String::from("I-LOC"),
 //!             offset: Offset { begin: 26, end: 31 },
+//!             token_start: 9,
+//!             token_end: 10,
 //!         },
 //!     ],
 //!     [
@@ -492,12 +504,16 @@
 //!             score: 0.9988,
 //!             label: String::from("I-LOC"),
 //!             offset: Offset { begin: 0, end: 5 },
+//!             token_start: 1,
+//!             token_end: 2,
 //!         },
 //!         Entity {
 //!             word: String::from("France"),
 //!             score: 0.9993,
 //!             label: String::from("I-LOC"),
 //!             offset: Offset { begin: 19, end: 25 },
+//!             token_start: 6,
+//!             token_end: 7,
 //!         },
 //!     ],
 //! ]
@@ -664,6 +680,10 @@
 //! Pretrained models are available on Hugging face's [model hub](https://huggingface.co/models?filter=rust) and can be loaded using `RemoteResources` defined in this library.
 //! A conversion utility script is included in `./utils` to convert Pytorch weights to a set of weights compatible with this library. This script requires Python and `torch` to be set-up, and can be used as follows:
 //! `python ./utils/convert_model.py path/to/pytorch_model.bin` where `path/to/pytorch_model.bin` is the location of the original Pytorch weights.
+//! Weights distributed in the safetensors format (`model.safetensors`) can be converted without Python using the `convert-tensor` binary:
+//! `cargo run --bin=convert-tensor -- path/to/model.safetensors path/to/rust_model.ot`, or programmatically with `rust_bert::conversion::convert_safetensors`.
+//! Alternatively, the token and sequence classification pipelines load safetensors weights directly when the model resource points to a file with the `.safetensors` extension,
+//! and `rust_bert::conversion::load_safetensors` loads them into the `VarStore` of a custom model.
 //!
 //!
 //! ## Async execution
@@ -726,6 +746,7 @@ pub mod roberta;
 pub mod t5;
 pub mod xlnet;
 
+pub use common::conversion;
 pub use common::error::RustBertError;
 pub use common::resources;
-pub use common::{Activation, Config};
+pub use common::{cuda_device_count, cuda_is_available, select_device, Activation, Config};
@@ -55,6 +55,43 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::path::Path;
+use tch::Device;
+
+/// # Common interface of the pipelines
+/// Provides a uniform way to run predictions and manage the device placement of pipeline models, for libraries
+/// building on top of the individual pipelines.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use rust_bert::pipelines::common::Pipeline;
+/// use rust_bert::pipelines::ner::NERModel;
+///
+/// fn run<P: Pipeline<Input = str>>(pipeline: &P, texts: &[&str]) -> Vec<P::Output> {
+///     pipeline.predict(texts)
+/// }
+///
+/// let ner_model = NERModel::new(Default::default())?;
+/// let entities = run(&ner_model, &["My name is Amy. I live in Paris."]);
+/// # Ok(())
+/// # }
+/// ```
+pub trait Pipeline {
+    /// Type of a single input of the pipeline (e.g. `str` for text inputs)
+    type Input: ?Sized;
+    /// Type of the prediction returned for a single input
+    type Output;
+
+    /// Runs the pipeline on a batch of inputs, returning one output per input
+    fn predict(&self, input: &[&Self::Input]) -> Vec<Self::Output>;
+
+    /// Returns the device the model weights are placed on
+    fn device(&self) -> Device;
+
+    /// Moves the model weights to a device in place
+    fn to_device(&mut self, device: Device);
+}
 
 #[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
 /// # Identifies the type of model
@@ -69,8 +106,10 @@ pub enum ModelType {
     #[serde(alias = "roberta")]
     Roberta,
     XLMRoberta,
+    #[serde(alias = "electra")]
     Electra,
     Marian,
+    #[serde(alias = "mobilebert")]
     MobileBert,
     #[serde(alias = "t5")]
     T5,
@@ -84,6 +123,7 @@ pub enum ModelType {
     OpenAiGpt,
     Reformer,
     ProphetNet,
+    #[serde(alias = "longformer")]
     Longformer,
     Pegasus,
     GPTNeo,
@@ -190,8 +230,9 @@ pub enum TokenizerOption {
 
 impl ConfigOption {
     /// Interface method to load a configuration from file
+    /// If the configuration does not provide an `id2label` mapping, it is inferred from `label2id` when available.
     pub fn from_file<P: AsRef<Path>>(model_type: ModelType, path: P) -> Self {
-        match model_type {
+        let mut config = match model_type {
             ModelType::Bart => ConfigOption::Bart(BartConfig::from_file(path)),
             ModelType::Bert => ConfigOption::Bert(BertConfig::from_file(path)),
             ModelType::Deberta => ConfigOption::Deberta(DebertaConfig::from_file(path)),
@@ -220,89 +261,145 @@ impl ConfigOption {
                 ConfigOption::M2M100(M2M100Config::from_file(path))
             }
             ModelType::FNet => ConfigOption::FNet(FNetConfig::from_file(path)),
-        }
+        };
+        config.fill_id2label_from_label2id();
+        config
     }
 
-    pub fn get_label_mapping(&self) -> &HashMap<i64, String> {
-        match self {
-            Self::Bart(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::Bert(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::Deberta(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::DebertaV2(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::DistilBert(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::Electra(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::Marian(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::MobileBert(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::Albert(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::XLNet(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::Reformer(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::ProphetNet(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::Longformer(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::MBart(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::M2M100(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::FNet(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::Roberta(config) => config
-                .id2label
-                .as_ref()
-                .expect("No label dictionary (id2label) provided in configuration file"),
-            Self::T5(_) => panic!("T5 does not use a label mapping"),
-            Self::LongT5(_) => panic!("LongT5 does not use a label mapping"),
-            Self::OpenAiGpt(_) => panic!("OpenAI GPT does not use a label mapping"),
-            Self::GPT2(_) => panic!("GPT2 does not use a label mapping"),
-            Self::GPTJ(_) => panic!("GPT-J does not use a label mapping"),
-            Self::GPTNeo(_) => panic!("GPT-Neo does not use a label mapping"),
-            Self::Pegasus(_) => panic!("Pegasus does not use a label mapping"),
+    /// Sets the label mapping (`id2label` and `label2id`) of the configuration, overriding the mapping read
+    /// from the configuration file. Returns an error for models that do not use a label mapping.
+    pub fn set_label_mapping(
+        &mut self,
+        label_mapping: HashMap<i64, String>,
+    ) -> Result<(), RustBertError> {
+        let (id2label, label2id) = match self {
+            Self::Bart(config) | Self::Marian(config) => {
+                (&mut config.id2label, &mut config.label2id)
+            }
+            Self::Bert(config) | Self::Roberta(config) => {
+                (&mut config.id2label, &mut config.label2id)
+            }
+            Self::Deberta(config) => (&mut config.id2label, &mut config.label2id),
+            Self::DebertaV2(config) => (&mut config.id2label, &mut config.label2id),
+            Self::DistilBert(config) => (&mut config.id2label, &mut config.label2id),
+            Self::Electra(config) => (&mut config.id2label, &mut config.label2id),
+            Self::MobileBert(config) => (&mut config.id2label, &mut config.label2id),
+            Self::Albert(config) => (&mut config.id2label, &mut config.label2id),
+            Self::XLNet(config) => (&mut config.id2label, &mut config.label2id),
+            Self::Reformer(config) => (&mut config.id2label, &mut config.label2id),
+            Self::ProphetNet(config) => (&mut config.id2label, &mut config.label2id),
+            Self::Longformer(config) => (&mut config.id2label, &mut config.label2id),
+            Self::MBart(config) | Self::M2M100(config) => {
+                (&mut config.id2label, &mut config.label2id)
+            }
+            Self::FNet(config) => (&mut config.id2label, &mut config.label2id),
+            _ => {
+                return Err(RustBertError::InvalidConfigurationError(
+                    "This model type does not use a label mapping".to_string(),
+                ))
+            }
+        };
+        *label2id = Some(
+            label_mapping
+                .iter()
+                .map(|(id, label)| (label.clone(), *id))
+                .collect(),
+        );
+        *id2label = Some(label_mapping);
+        Ok(())
+    }
+
+    fn fill_id2label_from_label2id(&mut self) {
+        let (id2label, label2id) = match self {
+            Self::Bart(config) | Self::Marian(config) => (&mut config.id2label, &config.label2id),
+            Self::Bert(config) | Self::Roberta(config) => (&mut config.id2label, &config.label2id),
+            Self::Deberta(config) => (&mut config.id2label, &config.label2id),
+            Self::DebertaV2(config) => (&mut config.id2label, &config.label2id),
+            Self::DistilBert(config) => (&mut config.id2label, &config.label2id),
+            Self::Electra(config) => (&mut config.id2label, &config.label2id),
+            Self::MobileBert(config) => (&mut config.id2label, &config.label2id),
+            Self::Albert(config) => (&mut config.id2label, &config.label2id),
+            Self::XLNet(config) => (&mut config.id2label, &config.label2id),
+            Self::Reformer(config) => (&mut config.id2label, &config.label2id),
+            Self::ProphetNet(config) => (&mut config.id2label, &config.label2id),
+            Self::Longformer(config) => (&mut config.id2label, &config.label2id),
+            Self::MBart(config) | Self::M2M100(config) => (&mut config.id2label, &config.label2id),
+            Self::FNet(config) => (&mut config.id2label, &config.label2id),
+            _ => return,
+        };
+        if id2label.is_none() {
+            *id2label = label2id.as_ref().map(|label2id| {
+                label2id
+                    .iter()
+                    .map(|(label, id)| (*id, label.clone()))
+                    .collect()
+            });
         }
     }
 
+    pub fn get_label_mapping(&self) -> Result<&HashMap<i64, String>, RustBertError> {
+        let label_mapping = match self {
+            Self::Bart(config) => config.id2label.as_ref(),
+            Self::Bert(config) => config.id2label.as_ref(),
+            Self::Deberta(config) => config.id2label.as_ref(),
+            Self::DebertaV2(config) => config.id2label.as_ref(),
+            Self::DistilBert(config) => config.id2label.as_ref(),
+            Self::Electra(config) => config.id2label.as_ref(),
+            Self::Marian(config) => config.id2label.as_ref(),
+            Self::MobileBert(config) => config.id2label.as_ref(),
+            Self::Albert(config) => config.id2label.as_ref(),
+            Self::XLNet(config) => config.id2label.as_ref(),
+            Self::Reformer(config) => config.id2label.as_ref(),
+            Self::ProphetNet(config) => config.id2label.as_ref(),
+            Self::Longformer(config) => config.id2label.as_ref(),
+            Self::MBart(config) => config.id2label.as_ref(),
+            Self::M2M100(config) => config.id2label.as_ref(),
+            Self::FNet(config) => config.id2label.as_ref(),
+            Self::Roberta(config) => config.id2label.as_ref(),
+            Self::T5(_) => {
+                return Err(RustBertError::InvalidConfigurationError(
+                    "T5 does not use a label mapping".to_string(),
+                ))
+            }
+            Self::LongT5(_) => {
+                return Err(RustBertError::InvalidConfigurationError(
+                    "LongT5 does not use a label mapping".to_string(),
+                ))
+            }
+            Self::OpenAiGpt(_) => {
+                return Err(RustBertError::InvalidConfigurationError(
+                    "OpenAI GPT does not use a label mapping".to_string(),
+                ))
+            }
+            Self::GPT2(_) => {
+                return Err(RustBertError::InvalidConfigurationError(
+                    "GPT2 does not use a label mapping".to_string(),
+                ))
+            }
+            Self::GPTJ(_) => {
+                return Err(RustBertError::InvalidConfigurationError(
+                    "GPT-J does not use a label mapping".to_string(),
+                ))
+            }
+            Self::GPTNeo(_) => {
+                return Err(RustBertError::InvalidConfigurationError(
+                    "GPT-Neo does not use a label mapping".to_string(),
+                ))
+            }
+            Self::Pegasus(_) => {
+                return Err(RustBertError::InvalidConfigurationError(
+                    "Pegasus does not use a label mapping".to_string(),
+                ))
+            }
+        };
+        label_mapping.ok_or_else(|| {
+            RustBertError::InvalidConfigurationError(
+                "No label dictionary (id2label or label2id) provided in configuration file"
+                    .to_string(),
+            )
+        })
+    }
+
     pub fn get_max_len(&self) -> Option<i64> {
         match self {
             Self::Bart(config) => Some(config.max_position_embeddings),
@@ -1793,3 +1890,26 @@ impl TokenizerOption {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn id2label_inferred_from_label2id() {
+        let mut config = ConfigOption::Bert(BertConfig {
+            label2id: Some(HashMap::from([
+                ("O".to_string(), 0),
+                ("B-PER".to_string(), 1),
+            ])),
+            ..Default::default()
+        });
+
+        config.fill_id2label_from_label2id();
+
+        let label_mapping = config.get_label_mapping().unwrap();
+        assert_eq!(label_mapping.len(), 2);
+        assert_eq!(label_mapping[&0], "O");
+        assert_eq!(label_mapping[&1], "B-PER");
+    }
+}
@@ -58,11 +58,12 @@ use crate::{
     bert::{BertConfigResources, BertModelResources, BertVocabResources},
     resources::RemoteResource,
 };
+use log::warn;
 use rust_tokenizers::tokenizer::TruncationStrategy;
 use rust_tokenizers::TokenizedInput;
 use std::borrow::Borrow;
 use tch::nn::VarStore;
-use tch::{nn, no_grad, Device, Tensor};
+use tch::{nn, no_grad, Device, Kind, Tensor};
 
 #[derive(Debug, Clone)]
 /// Output container for masked language model pipeline.
@@ -75,6 +76,16 @@ pub struct MaskedToken {
     pub score: f64,
 }
 
+#[derive(Debug, Clone)]
+/// Most likely tokens for a masked position, generated by `MaskedLanguageModel::predict_topk`
+pub struct MaskPrediction {
+    /// Position of the mask token in the tokenized input (including special tokens)
+    pub position: usize,
+    /// Most likely tokens for the masked position, sorted by decreasing probability.
+    /// The token scores are probabilities over the vocabulary.
+    pub tokens: Vec<MaskedToken>,
+}
+
 /// # Configuration for MaskedLanguageModel
 /// Contains information regarding the model to load and device to place the model on.
 pub struct MaskedLanguageConfig {
@@ -360,6 +371,7 @@ pub struct MaskedLanguageModel {
     mask_token: Option<String>,
     var_store: VarStore,
     max_length: usize,
+    pad_id: i64,
 }
 
 impl MaskedLanguageModel {
@@ -409,12 +421,17 @@ impl MaskedLanguageModel {
             MaskedLanguageOption::new(config.model_type, var_store.root(), &model_config)?;
         var_store.load(weights_path)?;
         let mask_token = config.mask_token;
+        let pad_id = tokenizer.get_pad_id().unwrap_or_else(|| {
+            warn!("The tokenizer does not define a padding token, inputs will be padded with id 0");
+            0
+        });
         Ok(MaskedLanguageModel {
             tokenizer,
             language_encode,
             mask_token,
             var_store,
             max_length,
+            pad_id,
         })
     }
 
@@ -457,7 +474,7 @@ impl MaskedLanguageModel {
             .iter()
             .map(|input| input.token_ids.clone())
             .map(|mut input| {
-                input.extend(vec![0; max_len - input.len()]);
+                input.resize(max_len, self.pad_id);
                 input
             })
             .map(|input| Tensor::of_slice(&(input)))
@@ -501,8 +518,102 @@ impl MaskedLanguageModel {
     where
         S: AsRef<[&'a str]>,
     {
+        let (output, mask_token_mask) = self.forward_masked(input.as_ref())?;
+        let mut output_tokens = Vec::with_capacity(input.as_ref().len());
+        for input_id in 0..input.as_ref().len() as i64 {
+            let mut sequence_tokens = vec![];
+            let sequence_mask = mask_token_mask.get(input_id);
+            if bool::from(sequence_mask.any()) {
+                let mask_scores = output
+                    .get(input_id)
+                    .index_select(0, &sequence_mask.argwhere().squeeze_dim(1));
+                let (token_scores, token_ids) = mask_scores.max_dim(1, false);
+                for (id, score) in token_ids.iter::<i64>()?.zip(token_scores.iter::<f64>()?) {
+                    let text = self.tokenizer.decode(&[id], false, true);
+                    sequence_tokens.push(MaskedToken { text, id, score });
+                }
+            }
+            output_tokens.push(sequence_tokens);
+        }
+        Ok(output_tokens)
+    }
+
+    /// Returns the `top_k` most likely tokens for each masked position. Masked positions are identified
+    /// by the mask token id of the tokenizer.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to mask.
+    /// * `top_k` - Number of tokens to return for each masked position
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<MaskPrediction>>` containing the predictions for each masked position, for each input text
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::masked_language::MaskedLanguageModel;
+    /// //    Set-up model
+    /// let mask_language_model = MaskedLanguageModel::new(Default::default())?;
+    ///
+    /// //    Define input
+    /// let input = ["Looks like one [MASK] is missing"];
+    ///
+    /// //    Run model
+    /// let output = mask_language_model.predict_topk(&input, 5)?;
+    /// for prediction in &output[0] {
+    ///     println!("{:?}", prediction.tokens);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_topk<'a, S>(
+        &self,
+        input: S,
+        top_k: usize,
+    ) -> Result<Vec<Vec<MaskPrediction>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let (output, mask_token_mask) = self.forward_masked(input.as_ref())?;
+        let top_k = top_k.min(output.size()[2] as usize) as i64;
+        let mut predictions = Vec::with_capacity(input.as_ref().len());
+        for input_id in 0..input.as_ref().len() as i64 {
+            let mut sequence_predictions = vec![];
+            let sequence_mask = mask_token_mask.get(input_id);
+            let positions = sequence_mask
+                .argwhere()
+                .squeeze_dim(1)
+                .iter::<i64>()?
+                .collect::<Vec<i64>>();
+            for position in positions {
+                let probabilities = output.get(input_id).get(position).softmax(-1, Kind::Float);
+                let (token_scores, token_ids) = probabilities.topk(top_k, -1, true, true);
+                let tokens = token_ids
+                    .iter::<i64>()?
+                    .zip(token_scores.iter::<f64>()?)
+                    .map(|(id, score)| MaskedToken {
+                        text: self.tokenizer.decode(&[id], false, true),
+                        id,
+                        score,
+                    })
+                    .collect();
+                sequence_predictions.push(MaskPrediction {
+                    position: position as usize,
+                    tokens,
+                });
+            }
+            predictions.push(sequence_predictions);
+        }
+        Ok(predictions)
+    }
+
+    /// Runs the model, returning the vocabulary scores and a boolean tensor flagging the masked positions
+    fn forward_masked(&self, input: &[&str]) -> Result<(Tensor, Tensor), RustBertError> {
         let input_tensor = if let Some(mask_token) = &self.mask_token {
-            let input_with_replaced_mask = self.replace_mask_token(input.as_ref(), mask_token)?;
+            let input_with_replaced_mask = self.replace_mask_token(input, mask_token)?;
             self.prepare_for_model(
                 input_with_replaced_mask
                     .iter()
@@ -510,7 +621,7 @@ impl MaskedLanguageModel {
                     .collect::<Vec<&str>>(),
             )
         } else {
-            self.prepare_for_model(input.as_ref())
+            self.prepare_for_model(input)
         };
 
         let output = no_grad(|| {
@@ -533,23 +644,7 @@ impl MaskedLanguageModel {
                     "Tokenizer does not have a mask token id, Please use a tokenizer/model with a mask token.".into(),
                 ))?;
         let mask_token_mask = input_tensor.eq(mask_token_id);
-        let mut output_tokens = Vec::with_capacity(input.as_ref().len());
-        for input_id in 0..input.as_ref().len() as i64 {
-            let mut sequence_tokens = vec![];
-            let sequence_mask = mask_token_mask.get(input_id);
-            if bool::from(sequence_mask.any()) {
-                let mask_scores = output
-                    .get(input_id)
-                    .index_select(0, &sequence_mask.argwhere().squeeze_dim(1));
-                let (token_scores, token_ids) = mask_scores.max_dim(1, false);
-                for (id, score) in token_ids.iter::<i64>()?.zip(token_scores.iter::<f64>()?) {
-                    let text = self.tokenizer.decode(&[id], false, true);
-                    sequence_tokens.push(MaskedToken { text, id, score });
-                }
-            }
-            output_tokens.push(sequence_tokens);
-        }
-        Ok(output_tokens)
+        Ok((output, mask_token_mask))
     }
 }
 #[cfg(test)]
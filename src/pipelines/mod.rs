@@ -289,7 +289,7 @@
 //!     "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
 //!     "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
 //! ];
-//! let output = sentiment_model.predict(&input);
+//! let output = sentiment_model.predict(&input)?;
 //! # Ok(())
 //! # }
 //! ```
@@ -344,12 +344,16 @@
 //!             score: 0.9986,
 //!             label: String::from("I-PER"),
 //!             offset: Offset { begin: 11, end: 14 },
+//!             token_start: 4,
+//!             token_end: 5,
 //!         },
 //!         Entity {
 //!             word: String::from("Paris"),
 //!             score: 0.9985,
 //!             label: String::from("I-LOC"),
 //!             offset: Offset { begin: 26, end: 31 },
+//!             token_start: 9,
+//!             token_end: 10,
 //!         },
 //!     ],
 //!     [
@@ -358,12 +362,16 @@
 //!             score: 0.9988,
 //!             label: String::from("I-LOC"),
 //!             offset: Offset { begin: 0, end: 5 },
+//!             token_start: 1,
+//!             token_end: 2,
 //!         },
 //!         Entity {
 //!             word: String::from("France"),
 //!             score: 0.9993,
 //!             label: String::from("I-LOC"),
 //!             offset: Offset { begin: 19, end: 25 },
+//!             token_start: 6,
+//!             token_end: 7,
 //!         },
 //!     ],
 //! ]
@@ -480,6 +488,9 @@ pub mod generation_utils;
 pub mod keywords_extraction;
 pub mod masked_language;
 pub mod ner;
+pub mod nested_ner;
+#[cfg(feature = "onnx")]
+pub mod onnx;
 pub mod pos_tagging;
 pub mod question_answering;
 pub mod sentence_embeddings;
@@ -51,12 +51,16 @@
 //!             score: 0.9986,
 //!             label: String::from("I-PER"),
 //!             offset: Offset { begin: 11, end: 14 },
+//!             token_start: 4,
+//!             token_end: 5,
 //!         },
 //!         Entity {
 //!             word: String::from("Paris"),
 //!             score: 0.9985,
 //!             label: String::from("I-LOC"),
 //!             offset: Offset { begin: 26, end: 31 },
+//!             token_start: 9,
+//!             token_end: 10,
 //!         },
 //!     ],
 //!     [
@@ -65,12 +69,16 @@
 //!             score: 0.9988,
 //!             label: String::from("I-LOC"),
 //!             offset: Offset { begin: 0, end: 5 },
+//!             token_start: 1,
+//!             token_end: 2,
 //!         },
 //!         Entity {
 //!             word: String::from("France"),
 //!             score: 0.9993,
 //!             label: String::from("I-LOC"),
 //!             offset: Offset { begin: 19, end: 25 },
+//!             token_start: 6,
+//!             token_end: 7,
 //!         },
 //!     ],
 //! ]
@@ -125,16 +133,42 @@
 //! German| XLM_ROBERTA_NER_DE |
 //! Spanish| XLM_ROBERTA_NER_ES |
 //! Dutch| XLM_ROBERTA_NER_NL |
+//!
+//! The tokenizer is selected from the `model_type` of the configuration, so that models relying on a
+//! byte-level BPE tokenizer (e.g. `ModelType::Roberta`, requiring a `merges_resource` in addition to
+//! the vocabulary) can be used in the same way as WordPiece-based models. Entity words are
+//! reconstructed from the token offsets in the original input, independently of the sub-word
+//! prefixes (`##`, `Ġ`) used by the tokenizer. Out-of-vocabulary pieces mapped to the unknown token
+//! (e.g. `[UNK]`) therefore keep their original surface form in the entity word.
+//! Tokens of an entity are only separated by a space if they are separated in the input, so that entities
+//! in scripts written without spaces (e.g. Japanese or Chinese with multilingual BERT) are reconstructed as written.
 
 use crate::common::error::RustBertError;
+use crate::pipelines::common::{ConfigOption, ModelType, Pipeline};
 use crate::pipelines::token_classification::{
-    Token, TokenClassificationConfig, TokenClassificationModel,
+    Timings, Token, TokenClassificationConfig, TokenClassificationModel, TokenEntropy,
+    TokenLabelScores, TokenizationDebug,
 };
-use rust_tokenizers::Offset;
+use crate::resources::ResourceProvider;
+use rust_tokenizers::{Mask, Offset};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hash;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+#[cfg(feature = "async")]
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tch::nn::VarStore;
+use tch::{nn, Device, Tensor};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 /// # Entity generated by a `NERModel`
+/// Entities implement `PartialEq` with an exact comparison of all fields, including the score.
+/// Results produced by different devices or batch compositions may differ slightly in score:
+/// compare these with a tolerance instead.
 pub struct Entity {
     /// String representation of the Entity
     pub word: String,
@@ -142,47 +176,1186 @@ pub struct Entity {
     pub score: f64,
     /// Entity label (e.g. ORG, LOC...)
     pub label: String,
-    /// Token offsets
+    /// Entity offsets in the original input, expressed in characters (Unicode scalar values).
+    /// For entities spanning multiple sub-tokens, the offset covers the entire span.
+    /// The span is half-open: `begin` is the first character of the entity and `end` the character following it
+    /// (see `as_range` and `span` for other conventions).
     pub offset: Offset,
+    /// Position of the first token of the entity in the tokenized input (including special tokens)
+    pub token_start: usize,
+    /// Position following the last token of the entity in the tokenized input (exclusive)
+    pub token_end: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+/// # Convention for the end of an entity span
+pub enum SpanEnd {
+    /// The end is the position following the last character of the entity, as in Rust ranges `start..end` (default)
+    #[default]
+    Exclusive,
+    /// The end is the position of the last character of the entity
+    Inclusive,
+}
+
+impl Entity {
+    /// Returns the half-open range of characters of the entity in the original input (`begin..end`).
+    /// The range is expressed in characters: convert character positions to byte positions
+    /// (e.g. with `str::char_indices`) before slicing the input.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = "My name is Amy. I live in Paris.";
+    /// let entities = ner_model.predict(&[input]);
+    /// let chars = input.chars().collect::<Vec<char>>();
+    /// for entity in &entities[0] {
+    ///     let surface_form = chars[entity.as_range()].iter().collect::<String>();
+    ///     println!("{surface_form}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn as_range(&self) -> Range<usize> {
+        self.offset.begin as usize..self.offset.end as usize
+    }
+
+    /// Returns the range of bytes of the entity in the original input, that can be used to slice the input string.
+    /// Character offsets beyond the end of the input are clamped to the input length, so that the range always
+    /// lies on character boundaries and slicing never panics.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&str` text the entity was extracted from
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = "My name is Amélie 🦀. I live in Paris.";
+    /// for entity in &ner_model.predict(&[input])[0] {
+    ///     println!("{}", &input[entity.byte_range(input)]);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn byte_range(&self, input: &str) -> Range<usize> {
+        let char_to_byte = |char_position: usize| {
+            input
+                .char_indices()
+                .nth(char_position)
+                .map_or(input.len(), |(byte_position, _)| byte_position)
+        };
+        let range = self.as_range();
+        let start = char_to_byte(range.start);
+        let end = char_to_byte(range.end).max(start);
+        start..end
+    }
+
+    /// Returns the start and end character positions of the entity in the original input, with the end following
+    /// the given convention.
+    ///
+    /// # Arguments
+    ///
+    /// * `span_end` - `SpanEnd` convention for the end position (exclusive, as in `as_range`, or inclusive)
+    ///
+    /// # Returns
+    ///
+    /// * `(usize, usize)` start and end positions of the entity (in characters)
+    pub fn span(&self, span_end: SpanEnd) -> (usize, usize) {
+        let range = self.as_range();
+        match span_end {
+            SpanEnd::Exclusive => (range.start, range.end),
+            SpanEnd::Inclusive => (range.start, range.end.saturating_sub(1).max(range.start)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// # Entities of an input at two levels of granularity, generated by `NERModel::predict_detailed`
+pub struct DetailedEntities {
+    /// Entities merged over consecutive tokens, as returned by `predict_full_entities`
+    pub entities: Vec<Entity>,
+    /// Entities of the individual word pieces, before the consolidation of sub-tokens
+    pub token_entities: Vec<Entity>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// # Entities of an input with a confidence for the whole input, generated by `NERModel::predict_grouped_with_confidence`
+pub struct SentenceEntities {
+    /// Entities extracted from the input, as returned by `predict`
+    pub entities: Vec<Entity>,
+    /// Aggregate probability of the labels of the tokens predicted as part of an entity (non-`O` labels),
+    /// or `None` if no token of the input was predicted as part of an entity
+    pub confidence: Option<f64>,
 }
 
 //type alias for some backward compatibility
 type NERConfig = TokenClassificationConfig;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+/// # Strategy combining the scores of the tokens of an entity into the entity score
+/// Used by `predict_full_entities` when merging several tokens into a single entity.
+pub enum ScoreAggregation {
+    /// Score of the first token of the entity
+    First,
+    /// Mean of the token scores
+    Mean,
+    /// Minimum token score
+    Min,
+    /// Product of the token scores (default). Longer entities get lower scores.
+    #[default]
+    Product,
+}
+
+impl ScoreAggregation {
+    fn aggregate(&self, scores: &[f64]) -> f64 {
+        match self {
+            ScoreAggregation::First => scores.first().copied().unwrap_or(0.0),
+            ScoreAggregation::Mean => {
+                if scores.is_empty() {
+                    0.0
+                } else {
+                    scores.iter().sum::<f64>() / scores.len() as f64
+                }
+            }
+            ScoreAggregation::Min => scores.iter().copied().fold(f64::INFINITY, f64::min),
+            ScoreAggregation::Product => scores.iter().product(),
+        }
+    }
+
+    /// Aggregates log-probabilities, returning the logarithm of the aggregate of the probabilities
+    fn aggregate_log(&self, log_scores: &[f64]) -> f64 {
+        match self {
+            ScoreAggregation::First | ScoreAggregation::Min => self.aggregate(log_scores),
+            ScoreAggregation::Mean => {
+                if log_scores.is_empty() {
+                    f64::NEG_INFINITY
+                } else {
+                    let max_score = log_scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                    let sum = log_scores
+                        .iter()
+                        .map(|score| (score - max_score).exp())
+                        .sum::<f64>();
+                    max_score + (sum / log_scores.len() as f64).ln()
+                }
+            }
+            ScoreAggregation::Product => log_scores.iter().sum(),
+        }
+    }
+}
+
 /// # NERModel to extract named entities
+/// `NERModel` is `Send` and `Sync`: prediction only requires a shared reference, so a single
+/// model can be wrapped in an `Arc` and used concurrently from multiple threads without reloading its weights.
 pub struct NERModel {
     token_classification_model: TokenClassificationModel,
+    score_aggregation: ScoreAggregation,
 }
 
-impl NERModel {
-    /// Build a new `NERModel`
+impl NERModel {
+    /// Build a new `NERModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `ner_config` - `NERConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(ner_config: NERConfig) -> Result<NERModel, RustBertError> {
+        let model = TokenClassificationModel::new(ner_config)?;
+        Ok(NERModel {
+            token_classification_model: model,
+            score_aggregation: ScoreAggregation::default(),
+        })
+    }
+
+    /// Set the strategy used to combine token scores into the score of the entities returned by
+    /// `predict_full_entities` (default: `ScoreAggregation::Product`).
+    ///
+    /// # Arguments
+    ///
+    /// * `score_aggregation` - `ScoreAggregation` strategy to use
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::ner::{NERModel, ScoreAggregation};
+    ///
+    /// let mut ner_model = NERModel::new(Default::default())?;
+    /// ner_model.set_score_aggregation(ScoreAggregation::Mean);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_score_aggregation(&mut self, score_aggregation: ScoreAggregation) {
+        self.score_aggregation = score_aggregation;
+    }
+
+    /// Build a new `NERModel` from a local model directory
+    ///
+    /// # Arguments
+    ///
+    /// * `model_dir` - Path to a directory containing `config.json`, `rust_model.ot` and the tokenizer vocabulary (see `TokenClassificationConfig::from_dir`)
+    /// * `device` - Device to place the model on
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::ner::NERModel;
+    /// use tch::Device;
+    ///
+    /// let ner_model = NERModel::from_dir("path/to/bert-ner", Device::cuda_if_available())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_dir<P: AsRef<Path>>(
+        model_dir: P,
+        device: Device,
+    ) -> Result<NERModel, RustBertError> {
+        let mut ner_config = NERConfig::from_dir(model_dir)?;
+        ner_config.device = device;
+        NERModel::new(ner_config)
+    }
+
+    /// Build a new `NERModel` from a model repository of the Hugging Face model hub, downloading the model files
+    /// to the local cache if they are not already available (see `TokenClassificationConfig::from_pretrained`)
+    ///
+    /// # Arguments
+    ///
+    /// * `model_id` - Identifier of the model repository (e.g. `dbmdz/bert-large-cased-finetuned-conll03-english`)
+    /// * `device` - Device to place the model on
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::ner::NERModel;
+    /// use tch::Device;
+    ///
+    /// let ner_model = NERModel::from_pretrained(
+    ///     "dbmdz/bert-large-cased-finetuned-conll03-english",
+    ///     Device::cuda_if_available(),
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "remote")]
+    pub fn from_pretrained(model_id: &str, device: Device) -> Result<NERModel, RustBertError> {
+        let mut ner_config = NERConfig::from_pretrained(model_id)?;
+        ner_config.device = device;
+        NERModel::new(ner_config)
+    }
+
+    /// Get a reference to the model configuration (e.g. hidden size, number of layers)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    /// use rust_bert::pipelines::common::ConfigOption;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// if let ConfigOption::Bert(config) = ner_model.get_config() {
+    ///     println!("hidden size: {}", config.hidden_size);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_config(&self) -> &ConfigOption {
+        self.token_classification_model.get_config()
+    }
+
+    /// Get a reference to the mapping from label indices to entity labels (e.g. `I-PER`)
+    pub fn get_label_mapping(&self) -> &HashMap<i64, String> {
+        self.token_classification_model.get_label_mapping()
+    }
+
+    /// Get the entity types the model can recognize (e.g. `LOC`, `MISC`, `ORG`, `PER`), sorted alphabetically.
+    /// Chunk prefixes (e.g. `B-`, `I-`) are stripped from the labels and the outside label `O` is excluded.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let entity_types = ner_model.entity_types();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn entity_types(&self) -> Vec<String> {
+        self.get_label_mapping()
+            .values()
+            .map(|label| entity_type(label))
+            .filter(|entity_type| !entity_type.is_empty())
+            .collect::<BTreeSet<&str>>()
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Run a forward pass on a small fixed input to trigger the lazy initialization of the backend
+    /// (e.g. CUDA kernels loading and memory allocation). Calling this method once after construction
+    /// avoids a latency spike on the first call to `predict` and stabilizes subsequent timings.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// ner_model.warmup();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn warmup(&self) {
+        let _ = self.token_classification_model.predict(
+            &["My name is Amy. I live in Paris."],
+            true,
+            false,
+        );
+    }
+
+    /// Cast the model weights to half precision (`Kind::Half`), reducing memory usage and speeding up
+    /// inference on GPU. Entity scores are still computed in single precision for numerical stability.
+    pub fn half(&mut self) {
+        self.token_classification_model.half();
+    }
+
+    /// Cast the model weights to single precision (`Kind::Float`)
+    pub fn float(&mut self) {
+        self.token_classification_model.float();
+    }
+
+    /// Move the model weights between devices in place, without reloading the model. This can be used
+    /// to temporarily evict a model from GPU memory and bring it back later.
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - Device to move the model weights to
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::ner::NERModel;
+    /// use tch::Device;
+    ///
+    /// let mut ner_model = NERModel::new(Default::default())?;
+    /// ner_model.to_device(Device::Cpu);
+    /// // ... GPU memory is now available for other models
+    /// ner_model.to_device(Device::cuda_if_available());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_device(&mut self, device: Device) {
+        self.token_classification_model.to_device(device);
+    }
+
+    /// Get the device the model weights are placed on
+    pub fn device(&self) -> Device {
+        self.token_classification_model.device()
+    }
+
+    /// Get a reference to the variable store holding the model weights, for example to build an optimizer
+    /// for fine-tuning (see `train_step`)
+    pub fn var_store(&self) -> &VarStore {
+        self.token_classification_model.var_store()
+    }
+
+    /// Returns the number of parameters of the model (see `TokenClassificationModel::num_parameters`)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// println!("{:.1}M parameters", ner_model.num_parameters() as f64 / 1e6);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn num_parameters(&self) -> i64 {
+        self.token_classification_model.num_parameters()
+    }
+
+    /// Tokenizes an input without running the model (see `TokenClassificationModel::tokenize_debug`)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let tokenization = ner_model.tokenize_debug("My name is Amélie. I live in Paris.");
+    /// for (token, offset) in tokenization.tokens.iter().zip(tokenization.offsets.iter()) {
+    ///     println!("{token} {offset:?}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tokenize_debug(&self, input: &str) -> TokenizationDebug {
+        self.token_classification_model.tokenize_debug(input)
+    }
+
+    /// Get a mutable reference to the variable store holding the model weights, for example to save
+    /// fine-tuned weights
+    pub fn var_store_mut(&mut self) -> &mut VarStore {
+        self.token_classification_model.var_store_mut()
+    }
+
+    /// Runs a single fine-tuning step on a batch of labelled token ids (see `TokenClassificationModel::train_step`)
+    ///
+    /// # Arguments
+    ///
+    /// * `optimizer` - `tch::nn::Optimizer` built from the model variable store (see `var_store`)
+    /// * `input_ids` - Input tensor of shape (*batch size*, *sequence_length*)
+    /// * `attention_mask` - Mask tensor of shape (*batch size*, *sequence_length*)
+    /// * `labels` - Label index tensor of shape (*batch size*, *sequence_length*), -100 for ignored positions
+    ///
+    /// # Returns
+    ///
+    /// * `f64` loss value for the batch, before the weights update
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    /// use tch::nn::OptimizerConfig;
+    /// use tch::{nn, Tensor};
+    ///
+    /// let mut ner_model = NERModel::new(Default::default())?;
+    /// let mut optimizer = nn::Adam::default().build(ner_model.var_store(), 5e-5)?;
+    ///
+    /// let input_ids = Tensor::of_slice(&[101i64, 1422, 1271, 1110, 10280, 102]).unsqueeze(0);
+    /// let attention_mask = input_ids.ones_like();
+    /// let labels = Tensor::of_slice(&[-100i64, 0, 0, 0, 4, -100]).unsqueeze(0);
+    /// let loss = ner_model.train_step(&mut optimizer, &input_ids, &attention_mask, &labels);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn train_step(
+        &mut self,
+        optimizer: &mut nn::Optimizer,
+        input_ids: &Tensor,
+        attention_mask: &Tensor,
+        labels: &Tensor,
+    ) -> f64 {
+        self.token_classification_model
+            .train_step(optimizer, input_ids, attention_mask, labels)
+    }
+
+    /// Extract entities from a text. Special tokens (e.g. `[CLS]`, `[SEP]`) identified by the tokenizer
+    /// are never returned as entities, regardless of the label predicted by the model. Empty or whitespace-only
+    /// texts are not passed to the model and yield an empty list of entities.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Entity>>` containing extracted entities
+    ///
+    /// # Panics
+    ///
+    /// Panics if the forward pass of the model fails, for example when ONNX Runtime rejects the inputs of a model
+    /// run with the `onnx` feature. The other prediction methods share this behaviour.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = [
+    ///     "My name is Amy. I live in Paris.",
+    ///     "Paris is a city in France.",
+    /// ];
+    /// let output = ner_model.predict(&input);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict<S>(&self, input: &[S]) -> Vec<Vec<Entity>>
+    where
+        S: AsRef<str>,
+    {
+        tokens_to_entities(self.token_classification_model.predict(input, true, false))
+    }
+
+    /// Extract entities from a text into a caller-provided vector, allowing its capacity to be reused
+    /// across calls. The vector is cleared before being filled with the entities of all inputs, in input order.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[S]` Array of texts to extract entities from (e.g. `&str` or `String`).
+    /// * `out` - `&mut Vec<Entity>` vector receiving the extracted entities
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let mut entities = Vec::new();
+    /// for input in ["My name is Amy. I live in Paris.", "Paris is a city in France."] {
+    ///     ner_model.predict_into(&[input], &mut entities);
+    ///     // ... use entities
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_into<S>(&self, input: &[S], out: &mut Vec<Entity>)
+    where
+        S: AsRef<str>,
+    {
+        out.clear();
+        out.extend(
+            self.token_classification_model
+                .predict(input, true, false)
+                .into_iter()
+                .flatten()
+                .filter_map(token_to_entity),
+        );
+    }
+
+    /// Extract entities from a text, reporting progress after each batch processed by the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[S]` Array of texts to extract entities from (e.g. `&str` or `String`).
+    /// * `progress` - callback invoked after each batch with `(batches_done, total_batches)`
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Entity>>` containing extracted entities
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = [
+    ///     "My name is Amy. I live in Paris.",
+    ///     "Paris is a city in France.",
+    /// ];
+    /// let output = ner_model.predict_with_progress(&input, |done, total| {
+    ///     println!("{done}/{total} batches processed")
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_with_progress<S, P>(&self, input: &[S], progress: P) -> Vec<Vec<Entity>>
+    where
+        S: AsRef<str>,
+        P: FnMut(usize, usize),
+    {
+        tokens_to_entities(
+            self.token_classification_model
+                .predict_with_progress(input, true, false, progress),
+        )
+    }
+
+    /// Extract entities from a text, stopping early if cancellation is requested. The cancellation flag is
+    /// checked between the batches processed by the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[S]` Array of texts to extract entities from (e.g. `&str` or `String`).
+    /// * `cancel` - `&AtomicBool` flag, set to `true` (e.g. from another thread) to cancel the prediction
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Vec<Entity>>, RustBertError>` containing extracted entities, or `RustBertError::Cancelled`
+    /// if the prediction was cancelled
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    /// use std::sync::atomic::AtomicBool;
+    /// use std::sync::Arc;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let cancel = Arc::new(AtomicBool::new(false));
+    /// let input = ["My name is Amy. I live in Paris."];
+    /// let output = ner_model.predict_cancellable(&input, &cancel)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_cancellable<S>(
+        &self,
+        input: &[S],
+        cancel: &AtomicBool,
+    ) -> Result<Vec<Vec<Entity>>, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        Ok(tokens_to_entities(
+            self.token_classification_model
+                .predict_cancellable(input, true, false, cancel)?,
+        ))
+    }
+
+    /// Extract entities from a text, measuring the time spent tokenizing the inputs, in the forward pass of the model
+    /// and in post-processing (including the conversion of tokens to entities). `predict` is not instrumented.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    ///
+    /// # Returns
+    ///
+    /// * `(Vec<Vec<Entity>>, Timings)` Entities extracted from each input, and the time spent in each stage
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = ["My name is Amy. I live in Paris."];
+    /// let (output, timings) = ner_model.predict_with_timings(&input);
+    /// println!(
+    ///     "tokenize: {:?}, forward: {:?}, postprocess: {:?}",
+    ///     timings.tokenize, timings.forward, timings.postprocess
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_with_timings<S>(&self, input: &[S]) -> (Vec<Vec<Entity>>, Timings)
+    where
+        S: AsRef<str>,
+    {
+        let (tokens, mut timings) = self
+            .token_classification_model
+            .predict_with_timings(input, true, false);
+        let postprocess_start = Instant::now();
+        let entities = tokens_to_entities(tokens);
+        timings.postprocess += postprocess_start.elapsed();
+        (entities, timings)
+    }
+
+    /// Extract entities from a single text
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&str` Text to extract entities from.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Entity>` containing the entities extracted from the text
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let output = ner_model.predict_single("My name is Amy. I live in Paris.");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_single(&self, input: &str) -> Vec<Entity> {
+        self.predict(&[input]).pop().unwrap_or_default()
+    }
+
+    /// Returns the entropy of the label distribution of each (sub-)token of the inputs, quantifying the
+    /// uncertainty of the model (see `TokenClassificationModel::predict_entropy`). Special tokens are not returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[S]` Array of texts (e.g. `&str` or `String`).
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<TokenEntropy>>` containing the tokens with the entropy of their label distribution, for each input
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = ["My name is Amy. I live in Paris.", "Paris is a city in France."];
+    /// // Select the input the model is the least confident about
+    /// let max_entropy = ner_model
+    ///     .predict_entropy(&input)
+    ///     .iter()
+    ///     .map(|tokens| tokens.iter().map(|token| token.entropy).fold(0.0, f64::max))
+    ///     .collect::<Vec<f64>>();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_entropy<S>(&self, input: &[S]) -> Vec<Vec<TokenEntropy>>
+    where
+        S: AsRef<str>,
+    {
+        self.token_classification_model
+            .predict_entropy(input, false)
+    }
+
+    /// Extract entities from a text without blocking the asynchronous runtime. The prediction runs on the
+    /// blocking thread pool of the current tokio runtime (requires the `async` feature).
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `Vec<S>` Texts to extract entities from (e.g. `&'static str` or `String`).
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Vec<Entity>>, RustBertError>` containing extracted entities, or `RustBertError::Cancelled`
+    /// if the task was cancelled (e.g. when the runtime shuts down). A panic of the prediction is resumed on the
+    /// awaiting task.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # async fn run() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    /// use std::sync::Arc;
+    ///
+    /// let ner_model = Arc::new(NERModel::new(Default::default())?);
+    /// let input = vec![String::from("My name is Amy. I live in Paris.")];
+    /// let output = ner_model.predict_async(input).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn predict_async<S>(
+        self: Arc<Self>,
+        input: Vec<S>,
+    ) -> Result<Vec<Vec<Entity>>, RustBertError>
+    where
+        S: AsRef<str> + Send + 'static,
+    {
+        match tokio::task::spawn_blocking(move || self.predict(&input)).await {
+            Ok(output) => Ok(output),
+            Err(error) if error.is_cancelled() => Err(RustBertError::Cancelled),
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        }
+    }
+
+    /// Extract entities from pre-tokenized inputs, skipping the tokenization step. The token ids should not contain
+    /// special tokens. Since the original text is not available, character offsets cannot be computed and the
+    /// labelled tokens are returned instead of entities, with their text reconstructed by decoding the token ids.
+    /// As for `predict`, the input tensors are created on the device of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `token_ids` - `&[Vec<i64>]` Token ids (without special tokens) of each input, as produced by the model tokenizer.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Token>>` containing the tokens labelled as part of an entity for each input
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let token_ids = [vec![1422, 1271, 1110, 10280, 119]];
+    /// let output = ner_model.predict_from_tokens(&token_ids);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_from_tokens(&self, token_ids: &[Vec<i64>]) -> Vec<Vec<Token>> {
+        self.token_classification_model
+            .predict_from_token_ids(token_ids, false)
+            .into_iter()
+            .map(|sequence_tokens| {
+                sequence_tokens
+                    .into_iter()
+                    .filter(|token| (token.label != "O") & (token.mask != Mask::Special))
+                    .collect::<Vec<Token>>()
+            })
+            .collect::<Vec<Vec<Token>>>()
+    }
+
+    /// Extract entities from a text, discarding entities with a confidence score below a threshold
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[S]` Array of texts to extract entities from (e.g. `&str` or `String`).
+    /// * `min_score` - `f64` minimum confidence score for an entity to be returned (a log-probability if
+    /// `log_probabilities` is set in the model configuration)
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Entity>>` containing extracted entities with a score greater or equal to `min_score`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = [
+    ///     "My name is Amy. I live in Paris.",
+    ///     "Paris is a city in France.",
+    /// ];
+    /// let output = ner_model.predict_with_threshold(&input, 0.9);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_with_threshold<S>(&self, input: &[S], min_score: f64) -> Vec<Vec<Entity>>
+    where
+        S: AsRef<str>,
+    {
+        self.predict(input)
+            .into_iter()
+            .map(|sequence_entities| {
+                sequence_entities
+                    .into_iter()
+                    .filter(|entity| entity.score >= min_score)
+                    .collect::<Vec<Entity>>()
+            })
+            .collect::<Vec<Vec<Entity>>>()
+    }
+
+    /// Extract entities of the given types from a text. Entity types are matched on the label with its chunk
+    /// prefix stripped (e.g. `LOC` matches both `B-LOC` and `I-LOC`). An empty slice of types returns all entities.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[S]` Array of texts to extract entities from (e.g. `&str` or `String`).
+    /// * `types` - `&[&str]` Entity types to keep (e.g. `["ORG", "LOC"]`)
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Entity>>` containing extracted entities of the given types
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = ["My name is Amy. I live in Paris."];
+    /// let output = ner_model.predict_filtered(&input, &["LOC"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_filtered<S>(&self, input: &[S], types: &[&str]) -> Vec<Vec<Entity>>
+    where
+        S: AsRef<str>,
+    {
+        let entities = self.predict(input);
+        if types.is_empty() {
+            return entities;
+        }
+        entities
+            .into_iter()
+            .map(|sequence_entities| {
+                sequence_entities
+                    .into_iter()
+                    .filter(|entity| types.contains(&entity_type(&entity.label)))
+                    .collect::<Vec<Entity>>()
+            })
+            .collect::<Vec<Vec<Entity>>>()
+    }
+
+    /// Extract entities from texts tagged with the identifier of the document they originate from (for example
+    /// documents split into sentences), grouping the results by document. All texts are processed in a single
+    /// call to the model, regardless of their document.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[(K, S)]` Array of (document identifier, text) pairs. The texts of a document do not need to be contiguous.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<(K, Vec<Vec<Entity>>)>` one entry per document, in order of first appearance of the document identifier,
+    /// holding the entities extracted from each text of the document in input order. Entity offsets are relative to
+    /// the text they were extracted from.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = [
+    ///     (1, "My name is Amy."),
+    ///     (1, "I live in Paris."),
+    ///     (2, "Paris is a city in France."),
+    /// ];
+    /// for (document_id, sentences) in ner_model.predict_grouped(&input) {
+    ///     println!("document {document_id}: {:?}", sentences.concat());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_grouped<K, S>(&self, input: &[(K, S)]) -> Vec<(K, Vec<Vec<Entity>>)>
+    where
+        K: Eq + Hash + Clone,
+        S: AsRef<str>,
+    {
+        let texts = input
+            .iter()
+            .map(|(_, text)| text.as_ref())
+            .collect::<Vec<&str>>();
+        group_by_document(input, self.predict(&texts))
+    }
+
+    /// Extract entities from texts tagged with the identifier of their document, grouped by document as in
+    /// `predict_grouped`, along with a confidence for each text. The confidence aggregates the probabilities of
+    /// the tokens predicted as part of an entity (non-`O` labels) and can be used to flag uncertain documents,
+    /// for example to route them to a human review.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[(K, S)]` Array of (document identifier, text) pairs. The texts of a document do not need to be contiguous.
+    /// * `confidence_aggregation` - `ScoreAggregation` strategy combining the token probabilities into the confidence of a text
+    /// (e.g. `ScoreAggregation::Mean` or `ScoreAggregation::Min`)
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<(K, Vec<SentenceEntities>)>` one entry per document, in order of first appearance of the document identifier,
+    /// holding the entities and confidence of each text of the document in input order. The confidence is a probability
+    /// (between 0 and 1), including for models returning log-probabilities.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::{NERModel, ScoreAggregation};
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = [
+    ///     (1, "My name is Amy."),
+    ///     (1, "I live in Paris."),
+    ///     (2, "Paris is a city in France."),
+    /// ];
+    /// for (document_id, sentences) in
+    ///     ner_model.predict_grouped_with_confidence(&input, ScoreAggregation::Min)
+    /// {
+    ///     if sentences
+    ///         .iter()
+    ///         .any(|sentence| sentence.confidence.map_or(false, |confidence| confidence < 0.8))
+    ///     {
+    ///         println!("document {document_id} needs a review");
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_grouped_with_confidence<K, S>(
+        &self,
+        input: &[(K, S)],
+        confidence_aggregation: ScoreAggregation,
+    ) -> Vec<(K, Vec<SentenceEntities>)>
+    where
+        K: Eq + Hash + Clone,
+        S: AsRef<str>,
+    {
+        let texts = input
+            .iter()
+            .map(|(_, text)| text.as_ref())
+            .collect::<Vec<&str>>();
+        let log_probabilities = self.token_classification_model.log_probabilities();
+        let sentences = self
+            .token_classification_model
+            .predict(&texts, true, false)
+            .into_iter()
+            .map(|sequence_tokens| {
+                let scores = sequence_tokens
+                    .iter()
+                    .filter(|token| token.get_tag() != Tag::Outside)
+                    .map(|token| {
+                        if log_probabilities {
+                            token.score.exp()
+                        } else {
+                            token.score
+                        }
+                    })
+                    .collect::<Vec<f64>>();
+                SentenceEntities {
+                    confidence: if scores.is_empty() {
+                        None
+                    } else {
+                        Some(confidence_aggregation.aggregate(&scores))
+                    },
+                    entities: sequence_tokens
+                        .into_iter()
+                        .filter_map(token_to_entity)
+                        .collect(),
+                }
+            })
+            .collect::<Vec<SentenceEntities>>();
+        group_by_document(input, sentences)
+    }
+
+    /// Lazily extract entities from a stream of texts. Inputs are pulled from the iterator and
+    /// processed `batch_size` texts at a time when the output is consumed, so that memory usage
+    /// does not grow with the size of the corpus.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - Iterator over the texts to extract entities from.
+    /// * `batch_size` - Number of texts processed in a single call to the model (values of 0 are treated as 1).
+    ///
+    /// # Returns
+    ///
+    /// * Iterator yielding the `Vec<Entity>` extracted for each input text, in order
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    /// # use std::fs::File;
+    /// # use std::io::{BufRead, BufReader};
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let corpus = BufReader::new(File::open("path/to/corpus.txt")?);
+    /// let lines = corpus.lines().map(|line| line.unwrap());
+    /// for entities in ner_model.predict_iter(lines, 32) {
+    ///     println!("{entities:?}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_iter<'a, I>(
+        &'a self,
+        inputs: I,
+        batch_size: usize,
+    ) -> impl Iterator<Item = Vec<Entity>> + 'a
+    where
+        I: Iterator<Item = String> + 'a,
+    {
+        let mut inputs = inputs;
+        let batch_size = batch_size.max(1);
+        let mut pending = Vec::new().into_iter();
+        std::iter::from_fn(move || loop {
+            if let Some(entities) = pending.next() {
+                return Some(entities);
+            }
+            let batch = inputs.by_ref().take(batch_size).collect::<Vec<String>>();
+            if batch.is_empty() {
+                return None;
+            }
+            pending = self.predict(&batch).into_iter();
+        })
+    }
+
+    /// Extract entities from texts received over a channel, for producer/consumer pipelines. Received texts are
+    /// batched until `batch_size` texts are available or `max_wait` has elapsed since the first text of the batch
+    /// was received, whichever comes first, and the batch is then processed in a single call to the model.
+    /// Blocks while waiting for texts; the iterator ends once all senders are dropped and the channel is drained.
     ///
     /// # Arguments
     ///
-    /// * `ner_config` - `NERConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    /// * `receiver` - `Receiver<String>` end of the channel the texts are sent to.
+    /// * `batch_size` - Maximum number of texts processed in a single call to the model (values of 0 are treated as 1).
+    /// * `max_wait` - Maximum time waited for a batch to fill up before it is processed.
+    ///
+    /// # Returns
+    ///
+    /// * Iterator yielding each received text with the `Vec<Entity>` extracted from it, in the order of reception
     ///
     /// # Example
     ///
     /// ```no_run
     /// # fn main() -> anyhow::Result<()> {
-    /// use rust_bert::pipelines::ner::NERModel;
+    /// # use rust_bert::pipelines::ner::NERModel;
+    /// # use std::sync::mpsc;
+    /// # use std::thread;
+    /// # use std::time::Duration;
     ///
     /// let ner_model = NERModel::new(Default::default())?;
+    /// let (sender, receiver) = mpsc::channel();
+    /// thread::spawn(move || {
+    ///     for sentence in ["My name is Amy.", "I live in Paris."] {
+    ///         sender.send(sentence.to_string()).unwrap();
+    ///     }
+    /// });
+    /// for (sentence, entities) in
+    ///     ner_model.predict_from_receiver(receiver, 32, Duration::from_millis(50))
+    /// {
+    ///     println!("{sentence}: {entities:?}");
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    pub fn new(ner_config: NERConfig) -> Result<NERModel, RustBertError> {
-        let model = TokenClassificationModel::new(ner_config)?;
-        Ok(NERModel {
-            token_classification_model: model,
+    pub fn predict_from_receiver(
+        &self,
+        receiver: Receiver<String>,
+        batch_size: usize,
+        max_wait: Duration,
+    ) -> impl Iterator<Item = (String, Vec<Entity>)> + '_ {
+        let batch_size = batch_size.max(1);
+        let mut pending = Vec::new().into_iter();
+        std::iter::from_fn(move || loop {
+            if let Some(output) = pending.next() {
+                return Some(output);
+            }
+            let mut batch = match receiver.recv() {
+                Ok(text) => vec![text],
+                Err(_) => return None,
+            };
+            let deadline = Instant::now() + max_wait;
+            while batch.len() < batch_size {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                match receiver.recv_timeout(timeout) {
+                    Ok(text) => batch.push(text),
+                    Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            let entities = self.predict(&batch);
+            pending = batch
+                .into_iter()
+                .zip(entities)
+                .collect::<Vec<(String, Vec<Entity>)>>()
+                .into_iter();
         })
     }
 
-    /// Extract entities from a text
+    /// Extract entities from a text, collapsing adjacent entities with identical word and label into a
+    /// single entity (for example when several word pieces of the same word are returned as separate entities).
+    /// Entities are adjacent when their token spans or character offsets touch: separate mentions of the same
+    /// word (e.g. "Paris and Paris") are kept as distinct entities.
+    /// The collapsed entity keeps the maximum score and spans the offsets of all the entities it replaces.
+    /// This is a lighter alternative to the entity chunking performed by `predict_full_entities`.
     ///
     /// # Arguments
     ///
-    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    /// * `input` - `&[S]` Array of texts to extract entities from (e.g. `&str` or `String`).
     ///
     /// # Returns
     ///
@@ -195,39 +1368,46 @@ impl NERModel {
     /// # use rust_bert::pipelines::ner::NERModel;
     ///
     /// let ner_model = NERModel::new(Default::default())?;
-    /// let input = [
-    ///     "My name is Amy. I live in Paris.",
-    ///     "Paris is a city in France.",
-    /// ];
-    /// let output = ner_model.predict(&input);
+    /// let input = ["My name is Amy. I live in Paris."];
+    /// let output = ner_model.predict_deduplicated(&input);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn predict<S>(&self, input: &[S]) -> Vec<Vec<Entity>>
+    pub fn predict_deduplicated<S>(&self, input: &[S]) -> Vec<Vec<Entity>>
     where
         S: AsRef<str>,
     {
-        self.token_classification_model
-            .predict(input, true, false)
+        self.predict(input)
             .into_iter()
-            .map(|sequence_tokens| {
-                sequence_tokens
-                    .into_iter()
-                    .filter(|token| token.label != "O")
-                    .map(|token| Entity {
-                        offset: token.offset.unwrap(),
-                        word: token.text,
-                        score: token.score,
-                        label: token.label,
-                    })
-                    .collect::<Vec<Entity>>()
-            })
+            .map(Self::deduplicate_entities)
             .collect::<Vec<Vec<Entity>>>()
     }
 
+    fn deduplicate_entities(entities: Vec<Entity>) -> Vec<Entity> {
+        let mut deduplicated_entities: Vec<Entity> = Vec::with_capacity(entities.len());
+        for entity in entities {
+            match deduplicated_entities.last_mut() {
+                Some(previous_entity)
+                    if (previous_entity.word == entity.word)
+                        && (previous_entity.label == entity.label)
+                        && ((previous_entity.token_end == entity.token_start)
+                            || (previous_entity.offset.end == entity.offset.begin)) =>
+                {
+                    previous_entity.score = previous_entity.score.max(entity.score);
+                    previous_entity.offset.end = entity.offset.end;
+                    previous_entity.token_end = entity.token_end;
+                }
+                _ => deduplicated_entities.push(entity),
+            }
+        }
+        deduplicated_entities
+    }
+
     /// Extract full entities from a text performing entity chunking. Follows the algorithm for entities
     /// chunking described in [Erik F. Tjong Kim Sang, Jorn Veenstra, Representing Text Chunks](https://www.aclweb.org/anthology/E99-1023/)
     /// The proposed implementation is inspired by the [Python seqeval library](https://github.com/chakki-works/seqeval) (shared under MIT license).
+    /// The score of each entity combines the scores of its tokens following the `ScoreAggregation` set with
+    /// `set_score_aggregation` (product of the token scores by default).
     ///
     /// # Arguments
     ///
@@ -264,12 +1444,16 @@ impl NERModel {
     ///         score: 0.9747,
     ///         label: String::from("PER"),
     ///         offset: Offset { begin: 6, end: 16 },
+    ///         token_start: 2,
+    ///         token_end: 4,
     ///     },
     ///     Entity {
     ///         word: String::from("Acme Corp"),
     ///         score: 0.8847,
     ///         label: String::from("I-LOC"),
     ///         offset: Offset { begin: 23, end: 32 },
+    ///         token_start: 5,
+    ///         token_end: 7,
     ///     },
     /// ]]
     /// # ;
@@ -282,17 +1466,78 @@ impl NERModel {
         let mut entities: Vec<Vec<Entity>> = Vec::new();
 
         for sequence_tokens in tokens {
-            entities.push(Self::consolidate_entities(&sequence_tokens));
+            entities.push(Self::consolidate_entities(
+                &sequence_tokens,
+                self.score_aggregation,
+                self.token_classification_model.log_probabilities(),
+            ));
         }
         entities
     }
 
-    fn consolidate_entities(tokens: &[Token]) -> Vec<Entity> {
+    /// Extract entities from a text, returning both the merged entities (as `predict_full_entities`) and the
+    /// entities of the individual word pieces they are built from. Both views are computed from a single
+    /// forward pass of the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[S]` Array of texts to extract entities from (e.g. `&str` or `String`).
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<DetailedEntities>` containing the merged and word piece entities of each input
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = ["Asked John Smith about Acme Corp"];
+    /// let output = ner_model.predict_detailed(&input);
+    /// for entity in &output[0].entities {
+    ///     println!("{} ({})", entity.word, entity.label);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_detailed<S>(&self, input: &[S]) -> Vec<DetailedEntities>
+    where
+        S: AsRef<str>,
+    {
+        let mut tokens = self.token_classification_model.predict(input, false, false);
+        let token_entities = tokens_to_entities(tokens.clone());
+        self.token_classification_model
+            .consolidate_sub_tokens(&mut tokens);
+        tokens
+            .iter()
+            .zip(token_entities)
+            .map(|(sequence_tokens, token_entities)| DetailedEntities {
+                entities: Self::consolidate_entities(
+                    sequence_tokens,
+                    self.score_aggregation,
+                    self.token_classification_model.log_probabilities(),
+                ),
+                token_entities,
+            })
+            .collect()
+    }
+
+    fn consolidate_entities(
+        tokens: &[Token],
+        score_aggregation: ScoreAggregation,
+        log_probabilities: bool,
+    ) -> Vec<Entity> {
         let mut entities: Vec<Entity> = Vec::new();
 
-        let mut entity_builder = EntityBuilder::new();
+        let mut entity_builder = EntityBuilder::new(score_aggregation, log_probabilities);
         for (position, token) in tokens.iter().enumerate() {
-            let tag = token.get_tag();
+            let tag = if token.mask == Mask::Special {
+                Tag::Outside
+            } else {
+                token.get_tag()
+            };
             let label = token.get_label();
             if let Some(entity) = entity_builder.handle_current_tag(tag, label, position, tokens) {
                 entities.push(entity)
@@ -305,14 +1550,165 @@ impl NERModel {
     }
 }
 
+/// # Builder for `NERModel`
+/// Chainable alternative to populating a `NERConfig` manually. Any option that is not set falls back
+/// to the default `NERConfig` (pretrained English BERT model on CoNLL03).
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use rust_bert::pipelines::ner::NERModelBuilder;
+/// use rust_bert::resources::LocalResource;
+/// use std::path::PathBuf;
+/// use tch::Device;
+///
+/// let ner_model = NERModelBuilder::new()
+///     .config(LocalResource::from(PathBuf::from("path/to/config.json")))
+///     .vocab(LocalResource::from(PathBuf::from("path/to/vocab.txt")))
+///     .weights(LocalResource::from(PathBuf::from("path/to/rust_model.ot")))
+///     .device(Device::Cpu)
+///     .max_length(256)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct NERModelBuilder {
+    config: NERConfig,
+    half_precision: bool,
+    score_aggregation: ScoreAggregation,
+}
+
+impl NERModelBuilder {
+    /// Create a new `NERModelBuilder` initialized with the default `NERConfig`
+    pub fn new() -> NERModelBuilder {
+        NERModelBuilder {
+            config: NERConfig::default(),
+            half_precision: false,
+            score_aggregation: ScoreAggregation::default(),
+        }
+    }
+
+    /// Set the model type (must match the resources provided)
+    pub fn model_type(mut self, model_type: ModelType) -> Self {
+        self.config.model_type = model_type;
+        self
+    }
+
+    /// Set the tokenizer vocabulary resource (e.g. vocab.txt/vocab.json)
+    pub fn vocab<R: ResourceProvider + Send + 'static>(mut self, vocab_resource: R) -> Self {
+        self.config.vocab_resource = Box::new(vocab_resource);
+        self
+    }
+
+    /// Set the tokenizer merges resource (e.g. merges.txt), needed only for BPE-based tokenizers
+    pub fn merges<R: ResourceProvider + Send + 'static>(mut self, merges_resource: R) -> Self {
+        self.config.merges_resource = Some(Box::new(merges_resource));
+        self
+    }
+
+    /// Set the model configuration resource (e.g. config.json)
+    pub fn config<R: ResourceProvider + Send + 'static>(mut self, config_resource: R) -> Self {
+        self.config.config_resource = Box::new(config_resource);
+        self
+    }
+
+    /// Set the model weights resource (e.g. rust_model.ot)
+    pub fn weights<R: ResourceProvider + Send + 'static>(mut self, model_resource: R) -> Self {
+        self.config.model_resource = Box::new(model_resource);
+        self
+    }
+
+    /// Set the device to place the model on
+    pub fn device(mut self, device: Device) -> Self {
+        self.config.device = device;
+        self
+    }
+
+    /// Set the maximum sequence length (in tokens) of each input feature
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.config.max_length = Some(max_length);
+        self
+    }
+
+    /// Set the number of tokens shared by consecutive windows for inputs longer than the maximum length
+    pub fn window_overlap(mut self, window_overlap: usize) -> Self {
+        self.config.window_overlap = Some(window_overlap);
+        self
+    }
+
+    /// Set the batch size used for predictions
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.config.batch_size = batch_size;
+        self
+    }
+
+    /// Set the number of tokenized inputs to keep in cache (caching is disabled by default)
+    pub fn tokenization_cache_size(mut self, cache_size: usize) -> Self {
+        self.config.tokenization_cache_size = Some(cache_size);
+        self
+    }
+
+    /// Set the lower casing flag of the tokenizer (for lower-cased models)
+    pub fn lower_case(mut self, lower_case: bool) -> Self {
+        self.config.lower_case = lower_case;
+        self
+    }
+
+    /// Set the accent stripping flag of the tokenizer (BERT models only). If not set, accents are stripped
+    /// for lower-cased models (see `TokenClassificationConfig::strip_accents`)
+    pub fn strip_accents(mut self, strip_accents: bool) -> Self {
+        self.config.strip_accents = Some(strip_accents);
+        self
+    }
+
+    /// Run the model in half precision (see `NERModel::half`)
+    pub fn half_precision(mut self, half_precision: bool) -> Self {
+        self.half_precision = half_precision;
+        self
+    }
+
+    /// Set the strategy combining token scores into entity scores for `predict_full_entities`
+    pub fn score_aggregation(mut self, score_aggregation: ScoreAggregation) -> Self {
+        self.score_aggregation = score_aggregation;
+        self
+    }
+
+    /// Set whether the special tokens of the model (e.g. `[CLS]` and `[SEP]`) are added to the inputs (enabled by default)
+    pub fn add_special_tokens(mut self, add_special_tokens: bool) -> Self {
+        self.config.add_special_tokens = add_special_tokens;
+        self
+    }
+
+    /// Load the `NERModel` from the resources set in the builder
+    pub fn build(self) -> Result<NERModel, RustBertError> {
+        let mut ner_model = NERModel::new(self.config)?;
+        ner_model.set_score_aggregation(self.score_aggregation);
+        if self.half_precision {
+            ner_model.half();
+        }
+        Ok(ner_model)
+    }
+}
+
+impl Default for NERModelBuilder {
+    fn default() -> Self {
+        NERModelBuilder::new()
+    }
+}
+
 struct EntityBuilder<'a> {
     previous_node: Option<(usize, Tag, &'a str)>,
+    score_aggregation: ScoreAggregation,
+    log_probabilities: bool,
 }
 
 impl<'a> EntityBuilder<'a> {
-    fn new() -> Self {
+    fn new(score_aggregation: ScoreAggregation, log_probabilities: bool) -> Self {
         EntityBuilder {
             previous_node: None,
+            score_aggregation,
+            log_probabilities,
         }
     }
 
@@ -350,21 +1746,31 @@ impl<'a> EntityBuilder<'a> {
         }
     }
 
+    fn aggregate_scores(&self, entity_tokens: &[Token]) -> f64 {
+        let scores = entity_tokens
+            .iter()
+            .map(|token| token.score)
+            .collect::<Vec<f64>>();
+        if self.log_probabilities {
+            self.score_aggregation.aggregate_log(&scores)
+        } else {
+            self.score_aggregation.aggregate(&scores)
+        }
+    }
+
     fn flush_and_reset(&mut self, position: usize, tokens: &[Token]) -> Option<Entity> {
         let entity = if let Some((start, _, label)) = self.previous_node {
             let entity_tokens = &tokens[start..position];
             Some(Entity {
-                word: entity_tokens
-                    .iter()
-                    .map(|token| token.text.as_str())
-                    .collect::<Vec<&str>>()
-                    .join(" "),
-                score: entity_tokens.iter().map(|token| token.score).product(),
+                word: join_token_texts(entity_tokens),
+                score: self.aggregate_scores(entity_tokens),
                 label: label.to_string(),
                 offset: Offset {
                     begin: entity_tokens.first()?.offset?.begin,
                     end: entity_tokens.last()?.offset?.end,
                 },
+                token_start: entity_tokens.first()?.index as usize,
+                token_end: entity_tokens.last()?.index_end as usize,
             })
         } else {
             None
@@ -389,24 +1795,297 @@ enum Tag {
 
 impl Token {
     fn get_tag(&self) -> Tag {
-        match self.label.split('-').collect::<Vec<&str>>()[0] {
-            "B" => Tag::Begin,
-            "I" => Tag::Inside,
-            "O" => Tag::Outside,
-            "E" => Tag::End,
-            "S" => Tag::Single,
-            _ => panic!("Invalid tag encountered for token {:?}", self),
+        match self.label.split_once('-') {
+            Some(("B", _)) => Tag::Begin,
+            Some(("I", _)) => Tag::Inside,
+            Some(("E", _)) | Some(("L", _)) => Tag::End,
+            Some(("S", _)) | Some(("U", _)) => Tag::Single,
+            _ if self.label == "O" => Tag::Outside,
+            // Labels without a chunk prefix (IO scheme) are treated as inside an entity
+            _ => Tag::Inside,
         }
     }
 
     fn get_label(&self) -> &str {
-        let split_label = self.label.split('-').collect::<Vec<&str>>();
-        if split_label.len() > 1 {
-            split_label[1]
-        } else {
-            ""
+        entity_type(&self.label)
+    }
+}
+
+/// Returns the entity type of a label, stripping the chunk prefix (e.g. `PER` for `B-PER`).
+/// Returns an empty string for the outside label `O`.
+fn entity_type(label: &str) -> &str {
+    match label.split_once('-') {
+        Some(("B", entity_type))
+        | Some(("I", entity_type))
+        | Some(("E", entity_type))
+        | Some(("L", entity_type))
+        | Some(("S", entity_type))
+        | Some(("U", entity_type)) => entity_type,
+        _ if label == "O" => "",
+        _ => label,
+    }
+}
+
+/// Renames the labels of extracted entities, for example to match an application taxonomy.
+/// Labels without an entry in the mapping are left unchanged. This can be applied to the output of any
+/// of the `NERModel` prediction methods (note that `predict_full_entities` returns labels without chunk prefix, e.g. `PER`).
+///
+/// # Arguments
+///
+/// * `entities` - Entities extracted by a `NERModel`, modified in place
+/// * `label_mapping` - `HashMap` from the model labels to the new labels
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use rust_bert::pipelines::ner::{map_labels, NERModel};
+/// use std::collections::HashMap;
+///
+/// let ner_model = NERModel::new(Default::default())?;
+/// let mut output = ner_model.predict(&["My name is Amy. I live in Paris."]);
+/// let label_mapping = HashMap::from([
+///     ("I-PER".to_string(), "PERSON".to_string()),
+///     ("I-LOC".to_string(), "PLACE".to_string()),
+/// ]);
+/// map_labels(&mut output, &label_mapping);
+/// # Ok(())
+/// # }
+/// ```
+pub fn map_labels(entities: &mut [Vec<Entity>], label_mapping: &HashMap<String, String>) {
+    for entity in entities.iter_mut().flatten() {
+        if let Some(label) = label_mapping.get(&entity.label) {
+            entity.label = label.clone();
+        }
+    }
+}
+
+/// # Ensemble of NER models
+/// Averages the label probability distributions of several `NERModel`s for each token before selecting the most
+/// likely label, which can make predictions more robust than those of a single fine-tuned model.
+/// The models must share the same label mapping and tokenize the inputs identically (for example models fine-tuned from
+/// the same base checkpoint), and return label probabilities (`log_probabilities` disabled).
+/// Sub-tokens are consolidated with the label aggregation function of the first model.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use rust_bert::pipelines::ner::{NEREnsemble, NERModel};
+/// use tch::Device;
+///
+/// let ensemble = NEREnsemble::new(vec![
+///     NERModel::from_dir("path/to/bert-ner-1", Device::cuda_if_available())?,
+///     NERModel::from_dir("path/to/bert-ner-2", Device::cuda_if_available())?,
+/// ])?;
+/// let output = ensemble.predict(&["My name is Amy. I live in Paris."])?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct NEREnsemble {
+    models: Vec<NERModel>,
+}
+
+impl NEREnsemble {
+    /// Build a new `NEREnsemble`
+    ///
+    /// # Arguments
+    ///
+    /// * `models` - `Vec<NERModel>` models of the ensemble
+    ///
+    /// # Returns
+    ///
+    /// * `NEREnsemble`, or a `RustBertError::InvalidConfigurationError` if no model is provided, if the label mappings
+    /// of the models differ or if a model returns log-probabilities
+    pub fn new(models: Vec<NERModel>) -> Result<NEREnsemble, RustBertError> {
+        let first_model = models.first().ok_or_else(|| {
+            RustBertError::InvalidConfigurationError(
+                "An ensemble requires at least one model".to_string(),
+            )
+        })?;
+        for (model_index, model) in models.iter().enumerate() {
+            if model.get_label_mapping() != first_model.get_label_mapping() {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "Incompatible label mappings: model {model_index} has labels {:?}, model 0 has labels {:?}",
+                    model.get_label_mapping(),
+                    first_model.get_label_mapping()
+                )));
+            }
+            if model.token_classification_model.log_probabilities() {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "Model {model_index} returns log-probabilities: ensembles average label probabilities"
+                )));
+            }
+        }
+        Ok(NEREnsemble { models })
+    }
+
+    /// Get the models of the ensemble
+    pub fn models(&self) -> &[NERModel] {
+        &self.models
+    }
+
+    /// Extract entities from a text, averaging the label probabilities of the models of the ensemble
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[S]` Array of texts to extract entities from (e.g. `&str` or `String`).
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Entity>>` containing extracted entities, or a `RustBertError::InvalidConfigurationError` if the
+    /// models tokenize the inputs differently
+    pub fn predict<S>(&self, input: &[S]) -> Result<Vec<Vec<Entity>>, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        let predictions = self
+            .models
+            .iter()
+            .map(|model| {
+                model
+                    .token_classification_model
+                    .predict_label_scores(input, false)
+            })
+            .collect::<Vec<Vec<Vec<TokenLabelScores>>>>();
+        let label_mapping = self.models[0].get_label_mapping();
+        let num_models = self.models.len() as f64;
+        let mut tokens = Vec::with_capacity(input.len());
+        for (sequence_index, sequence_scores) in predictions[0].iter().enumerate() {
+            let mut sequence_tokens = Vec::with_capacity(sequence_scores.len());
+            for (token_index, token_scores) in sequence_scores.iter().enumerate() {
+                let mut label_scores = token_scores.label_scores.clone();
+                for model_predictions in &predictions[1..] {
+                    let other_scores = model_predictions[sequence_index]
+                        .get(token_index)
+                        .filter(|other_scores| {
+                            (other_scores.token.offset == token_scores.token.offset)
+                                & (other_scores.label_scores.len() == label_scores.len())
+                        })
+                        .ok_or_else(|| {
+                            RustBertError::InvalidConfigurationError(
+                                "The models of the ensemble tokenize the inputs differently"
+                                    .to_string(),
+                            )
+                        })?;
+                    for (score, other_score) in
+                        label_scores.iter_mut().zip(&other_scores.label_scores)
+                    {
+                        *score += other_score;
+                    }
+                }
+                let (label_index, score) = label_scores
+                    .iter()
+                    .enumerate()
+                    .max_by(|(_, score_a), (_, score_b)| score_a.total_cmp(score_b))
+                    .map(|(label_index, score)| (label_index as i64, score / num_models))
+                    .unwrap_or((0, 0.0));
+                let mut token = token_scores.token.clone();
+                token.label = match label_mapping.get(&label_index) {
+                    Some(label) => label.clone(),
+                    None => continue,
+                };
+                token.label_index = label_index;
+                token.score = score;
+                sequence_tokens.push(token);
+            }
+            if predictions[1..].iter().any(|model_predictions| {
+                model_predictions[sequence_index].len() != sequence_scores.len()
+            }) {
+                return Err(RustBertError::InvalidConfigurationError(
+                    "The models of the ensemble tokenize the inputs differently".to_string(),
+                ));
+            }
+            tokens.push(sequence_tokens);
+        }
+        self.models[0]
+            .token_classification_model
+            .consolidate_sub_tokens(&mut tokens);
+        Ok(tokens_to_entities(tokens))
+    }
+}
+
+/// Converts consolidated tokens to entities, dropping the outside label and special tokens
+fn token_to_entity(token: Token) -> Option<Entity> {
+    if (token.label == "O") | (token.mask == Mask::Special) {
+        return None;
+    }
+    Some(Entity {
+        offset: token.offset?,
+        token_start: token.index as usize,
+        token_end: token.index_end as usize,
+        word: token.text,
+        score: token.score,
+        label: token.label,
+    })
+}
+
+/// Groups the outputs of texts tagged with a document identifier by document, in order of first appearance
+fn group_by_document<K, S, T>(input: &[(K, S)], outputs: Vec<T>) -> Vec<(K, Vec<T>)>
+where
+    K: Eq + Hash + Clone,
+{
+    let mut document_positions: HashMap<K, usize> = HashMap::new();
+    let mut grouped_outputs: Vec<(K, Vec<T>)> = Vec::new();
+    for ((document_id, _), output) in input.iter().zip(outputs) {
+        let position = *document_positions
+            .entry(document_id.clone())
+            .or_insert_with(|| {
+                grouped_outputs.push((document_id.clone(), Vec::new()));
+                grouped_outputs.len() - 1
+            });
+        grouped_outputs[position].1.push(output);
+    }
+    grouped_outputs
+}
+
+fn tokens_to_entities(tokens: Vec<Vec<Token>>) -> Vec<Vec<Entity>> {
+    tokens
+        .into_iter()
+        .map(|sequence_tokens| {
+            sequence_tokens
+                .into_iter()
+                .filter_map(token_to_entity)
+                .collect::<Vec<Entity>>()
+        })
+        .collect::<Vec<Vec<Entity>>>()
+}
+
+impl Pipeline for NERModel {
+    type Input = str;
+    type Output = Vec<Entity>;
+
+    fn predict(&self, input: &[&str]) -> Vec<Vec<Entity>> {
+        NERModel::predict(self, input)
+    }
+
+    fn device(&self) -> Device {
+        NERModel::device(self)
+    }
+
+    fn to_device(&mut self, device: Device) {
+        NERModel::to_device(self, device)
+    }
+}
+
+/// Joins the texts of the tokens of an entity. Tokens are separated by a space only if their offsets are not contiguous
+/// in the original input, so that words of scripts written without spaces (e.g. Japanese or Chinese, split into
+/// single characters by WordPiece tokenizers) are not separated.
+fn join_token_texts(tokens: &[Token]) -> String {
+    let mut word = String::new();
+    let mut previous_end = None;
+    for token in tokens {
+        let contiguous = match (previous_end, token.offset) {
+            (Some(previous_end), Some(offset)) => previous_end == offset.begin,
+            _ => false,
+        };
+        if !word.is_empty() && !contiguous {
+            word.push(' ');
         }
+        word.push_str(token.text.as_str());
+        previous_end = token.offset.map(|offset| offset.end);
     }
+    word
 }
 
 #[cfg(test)]
@@ -419,4 +2098,299 @@ mod test {
         let config = NERConfig::default();
         let _: Box<dyn Send> = Box::new(NERModel::new(config));
     }
+
+    #[test]
+    fn ner_model_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<NERModel>();
+    }
+
+    fn build_tokens(words_labels: &[(&str, &str)]) -> Vec<Token> {
+        let mut tokens = Vec::with_capacity(words_labels.len());
+        let mut begin = 0;
+        for (index, (word, label)) in words_labels.iter().enumerate() {
+            let end = begin + word.chars().count() as u32;
+            tokens.push(Token {
+                text: word.to_string(),
+                score: 0.9,
+                label: label.to_string(),
+                label_index: 0,
+                sentence: 0,
+                index: index as u16,
+                index_end: index as u16 + 1,
+                word_index: index as u16,
+                offset: Some(Offset { begin, end }),
+                mask: Default::default(),
+            });
+            begin = end + 1;
+        }
+        tokens
+    }
+
+    #[test]
+    fn consolidate_entities_chunk_schemes() {
+        let tokens = build_tokens(&[
+            ("Let's", "O"),
+            ("go", "O"),
+            ("to", "O"),
+            ("New", "I-LOC"),
+            ("York", "I-LOC"),
+            ("Paris", "B-LOC"),
+            ("and", "O"),
+            ("Acme", "U-ORG"),
+            ("John", "PER"),
+            ("Smith", "PER"),
+        ]);
+
+        let entities = NERModel::consolidate_entities(&tokens, ScoreAggregation::Product, false);
+
+        assert_eq!(entities.len(), 4);
+        assert_eq!(entities[0].word, "New York");
+        assert_eq!(entities[0].label, "LOC");
+        assert_eq!(entities[0].offset, Offset { begin: 12, end: 20 });
+        assert_eq!((entities[0].token_start, entities[0].token_end), (3, 5));
+        assert_eq!(entities[1].word, "Paris");
+        assert_eq!(entities[1].label, "LOC");
+        assert_eq!(entities[2].word, "Acme");
+        assert_eq!(entities[2].label, "ORG");
+        assert_eq!(entities[3].word, "John Smith");
+        assert_eq!(entities[3].label, "PER");
+    }
+
+    #[test]
+    fn consolidate_entities_without_spaces() {
+        //    "私は東京に住んでいます" tokenized into single characters by a multilingual WordPiece tokenizer
+        let mut tokens = build_tokens(&[
+            ("私", "O"),
+            ("は", "O"),
+            ("東", "B-LOC"),
+            ("京", "I-LOC"),
+            ("に", "O"),
+        ]);
+        for (index, token) in tokens.iter_mut().enumerate() {
+            token.offset = Some(Offset {
+                begin: index as u32,
+                end: index as u32 + 1,
+            });
+        }
+
+        let entities = NERModel::consolidate_entities(&tokens, ScoreAggregation::Product, false);
+
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].word, "東京");
+        assert_eq!(entities[0].label, "LOC");
+        assert_eq!(entities[0].offset, Offset { begin: 2, end: 4 });
+    }
+
+    #[test]
+    fn entity_span_conventions() {
+        let tokens = build_tokens(&[("I", "O"), ("live", "O"), ("in", "O"), ("Paris", "B-LOC")]);
+
+        let entities = NERModel::consolidate_entities(&tokens, ScoreAggregation::Product, false);
+
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].as_range(), 10..15);
+        assert_eq!(entities[0].span(SpanEnd::Exclusive), (10, 15));
+        assert_eq!(entities[0].span(SpanEnd::Inclusive), (10, 14));
+        let chars = "I live in Paris".chars().collect::<Vec<char>>();
+        assert_eq!(
+            chars[entities[0].as_range()].iter().collect::<String>(),
+            "Paris"
+        );
+    }
+
+    #[test]
+    fn entity_byte_range_multibyte_characters() {
+        //    Emoji (4 bytes) and combining acute accent (2 bytes) before the entity
+        let input = "🦀 cafe\u{301} Amélie";
+        let tokens = build_tokens(&[("🦀", "O"), ("cafe\u{301}", "O"), ("Amélie", "B-PER")]);
+
+        let entities = NERModel::consolidate_entities(&tokens, ScoreAggregation::Product, false);
+
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].as_range(), 8..14);
+        assert_eq!(&input[entities[0].byte_range(input)], "Amélie");
+
+        //    Offsets beyond the input are clamped on character boundaries
+        let mut entity = entities[0].clone();
+        entity.offset = Offset { begin: 8, end: 40 };
+        assert_eq!(&input[entity.byte_range(input)], "Amélie");
+        entity.offset = Offset { begin: 30, end: 40 };
+        assert_eq!(&input[entity.byte_range(input)], "");
+    }
+
+    #[test]
+    fn map_labels_renames_mapped_labels() {
+        let tokens = build_tokens(&[("Amy", "B-PER"), ("Paris", "B-LOC"), ("UN", "B-ORG")]);
+        let mut entities = vec![NERModel::consolidate_entities(
+            &tokens,
+            ScoreAggregation::Product,
+            false,
+        )];
+        let label_mapping = HashMap::from([
+            ("PER".to_string(), "PERSON".to_string()),
+            ("LOC".to_string(), "PLACE".to_string()),
+        ]);
+
+        map_labels(&mut entities, &label_mapping);
+
+        let labels = entities[0]
+            .iter()
+            .map(|entity| entity.label.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(labels, ["PERSON", "PLACE", "ORG"]);
+    }
+
+    #[test]
+    fn score_aggregation_strategies() {
+        let mut tokens = build_tokens(&[
+            ("Bank", "B-ORG"),
+            ("of", "I-ORG"),
+            ("New", "I-ORG"),
+            ("York", "I-ORG"),
+            ("Mellon", "I-ORG"),
+        ]);
+        for (token, score) in tokens.iter_mut().zip([0.9, 0.8, 0.9, 0.95, 0.7]) {
+            token.score = score;
+        }
+
+        let score = |score_aggregation| {
+            let entities = NERModel::consolidate_entities(&tokens, score_aggregation, false);
+            assert_eq!(entities.len(), 1);
+            entities[0].score
+        };
+
+        assert!((score(ScoreAggregation::First) - 0.9).abs() < 1e-9);
+        assert!((score(ScoreAggregation::Mean) - 0.85).abs() < 1e-9);
+        assert!((score(ScoreAggregation::Min) - 0.7).abs() < 1e-9);
+        assert!((score(ScoreAggregation::Product) - 0.9 * 0.8 * 0.9 * 0.95 * 0.7).abs() < 1e-9);
+        assert!(score(ScoreAggregation::Product) < score(ScoreAggregation::Mean));
+    }
+
+    #[test]
+    fn score_aggregation_log_probabilities() {
+        let scores = [0.9f64, 0.8, 0.95, 0.7];
+        let mut tokens = build_tokens(&[
+            ("Bank", "B-ORG"),
+            ("of", "I-ORG"),
+            ("New", "I-ORG"),
+            ("York", "I-ORG"),
+        ]);
+        for (token, score) in tokens.iter_mut().zip(scores) {
+            token.score = score.ln();
+        }
+
+        for score_aggregation in [
+            ScoreAggregation::First,
+            ScoreAggregation::Mean,
+            ScoreAggregation::Min,
+            ScoreAggregation::Product,
+        ] {
+            let entities = NERModel::consolidate_entities(&tokens, score_aggregation, true);
+            assert_eq!(entities.len(), 1);
+            let expected = score_aggregation.aggregate(&scores).ln();
+            assert!((entities[0].score - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn consolidate_entities_skips_special_tokens() {
+        let mut tokens =
+            build_tokens(&[("[CLS]", "B-PER"), ("Paris", "B-LOC"), ("[SEP]", "I-LOC")]);
+        tokens[0].mask = Mask::Special;
+        tokens[0].offset = None;
+        tokens[2].mask = Mask::Special;
+        tokens[2].offset = None;
+
+        let entities = NERModel::consolidate_entities(&tokens, ScoreAggregation::Product, false);
+
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].word, "Paris");
+        assert_eq!(entities[0].label, "LOC");
+    }
+
+    #[test]
+    fn deduplicate_adjacent_entities() {
+        let entity = |word: &str, label: &str, score: f64, token_start: usize| Entity {
+            word: word.to_string(),
+            score,
+            label: label.to_string(),
+            offset: Offset {
+                begin: token_start as u32,
+                end: token_start as u32 + 1,
+            },
+            token_start,
+            token_end: token_start + 1,
+        };
+        let entities = vec![
+            entity("Paris", "I-LOC", 0.8, 1),
+            entity("Paris", "I-LOC", 0.9, 2),
+            entity("Paris", "I-ORG", 0.7, 3),
+            entity("France", "I-LOC", 0.9, 4),
+        ];
+
+        let entities = NERModel::deduplicate_entities(entities);
+
+        assert_eq!(entities.len(), 3);
+        assert_eq!(entities[0].word, "Paris");
+        assert!((entities[0].score - 0.9).abs() < 1e-9);
+        assert_eq!(entities[0].offset, Offset { begin: 1, end: 3 });
+        assert_eq!((entities[0].token_start, entities[0].token_end), (1, 3));
+        assert_eq!(entities[1].label, "I-ORG");
+        assert_eq!(entities[2].word, "France");
+    }
+
+    #[test]
+    fn deduplicate_keeps_separate_identical_mentions() {
+        // "Paris and Paris"
+        let entity = |begin: u32, token_start: usize| Entity {
+            word: "Paris".to_string(),
+            score: 0.9,
+            label: "I-LOC".to_string(),
+            offset: Offset {
+                begin,
+                end: begin + 5,
+            },
+            token_start,
+            token_end: token_start + 1,
+        };
+        let entities = vec![entity(0, 1), entity(10, 3)];
+
+        let entities = NERModel::deduplicate_entities(entities);
+
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0].offset, Offset { begin: 0, end: 5 });
+        assert_eq!((entities[0].token_start, entities[0].token_end), (1, 2));
+        assert_eq!(entities[1].offset, Offset { begin: 10, end: 15 });
+        assert_eq!((entities[1].token_start, entities[1].token_end), (3, 4));
+    }
+
+    #[test]
+    fn entity_json_round_trip() {
+        let entity = Entity {
+            word: "Paris".to_string(),
+            score: 0.9986,
+            label: "I-LOC".to_string(),
+            offset: Offset { begin: 26, end: 31 },
+            token_start: 9,
+            token_end: 10,
+        };
+
+        let json = serde_json::to_value(&entity).unwrap();
+        assert_eq!(json["word"], "Paris");
+        assert_eq!(json["score"], 0.9986);
+        assert_eq!(json["label"], "I-LOC");
+
+        let deserialized: Entity = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized, entity);
+    }
+
+    #[test]
+    fn entity_type_strips_chunk_prefix() {
+        assert_eq!(entity_type("B-PER"), "PER");
+        assert_eq!(entity_type("I-LOC"), "LOC");
+        assert_eq!(entity_type("U-ORG"), "ORG");
+        assert_eq!(entity_type("MISC"), "MISC");
+        assert_eq!(entity_type("O"), "");
+    }
 }
@@ -0,0 +1,460 @@
+// Copyright 2023 The rust-bert contributors
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Nested Named Entity Recognition pipeline
+//! Extracts potentially overlapping entities (e.g. the location "China" inside the organization "Bank of China") from text.
+//! The BIO tagging scheme of the `NERModel` assigns a single label to each token and can therefore not represent
+//! nested entities. This pipeline instead classifies every candidate span of up to `max_span_width` tokens with a
+//! span classification head (`BertForSpanClassification`), and returns all the spans assigned an entity label
+//! with a score above `min_score`.
+//!
+//! Spans start and end at word boundaries. Among the retained spans, partially overlapping (crossing) spans are
+//! resolved by keeping the highest scoring one, while spans nested in one another are all returned.
+//!
+//! No pretrained span classification model is provided: the pipeline loads a BERT model fine-tuned for span
+//! classification, with an "O" (no entity) label in the `id2label` configuration mapping, and the span
+//! classification layer stored under the `span_classifier` variable names.
+//! Inputs longer than the maximum number of position embeddings of the model are truncated (with a warning).
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::nested_ner::{NestedNERConfig, NestedNERModel};
+//! use rust_bert::resources::LocalResource;
+//! use std::path::PathBuf;
+//!
+//! let config = NestedNERConfig::new(
+//!     LocalResource::from(PathBuf::from("path/to/rust_model.ot")),
+//!     LocalResource::from(PathBuf::from("path/to/config.json")),
+//!     LocalResource::from(PathBuf::from("path/to/vocab.txt")),
+//!     false,
+//! );
+//! let nested_ner_model = NestedNERModel::new(config)?;
+//!
+//! let input = ["He opened an account at the Bank of China."];
+//! let output = nested_ner_model.predict(&input)?;
+//! # Ok(())
+//! # }
+//! ```
+//! Output (the nested entities are both returned): \
+//! ```no_run
+//! # use rust_bert::pipelines::ner::Entity;
+//! # use rust_tokenizers::Offset;
+//! # let output =
+//! [[
+//!     Entity {
+//!         word: String::from("Bank of China"),
+//!         score: 0.9871,
+//!         label: String::from("ORG"),
+//!         offset: Offset { begin: 28, end: 41 },
+//!         token_start: 7,
+//!         token_end: 10,
+//!     },
+//!     Entity {
+//!         word: String::from("China"),
+//!         score: 0.9523,
+//!         label: String::from("LOC"),
+//!         offset: Offset { begin: 36, end: 41 },
+//!         token_start: 9,
+//!         token_end: 10,
+//!     },
+//! ]]
+//! # ;
+//! ```
+
+use crate::bert::{BertConfig, BertForSpanClassification};
+use crate::common::error::RustBertError;
+use crate::pipelines::common::{ModelType, TokenizerOption};
+use crate::pipelines::ner::Entity;
+use crate::resources::{load_weights, ResourceProvider};
+use crate::Config;
+use log::warn;
+use rust_tokenizers::{Mask, Offset, TokenIdsWithOffsets};
+use std::cmp::{min, Reverse};
+use std::collections::HashMap;
+use tch::nn::VarStore;
+use tch::{no_grad, Device, Kind, Tensor};
+
+/// # Configuration for NestedNERModel
+/// Contains information regarding the model to load and device to place the model on.
+pub struct NestedNERConfig {
+    /// Model weights resource
+    pub model_resource: Box<dyn ResourceProvider + Send>,
+    /// Config resource
+    pub config_resource: Box<dyn ResourceProvider + Send>,
+    /// Vocab resource
+    pub vocab_resource: Box<dyn ResourceProvider + Send>,
+    /// Automatically lower case all input upon tokenization (assumes a lower-cased model)
+    pub lower_case: bool,
+    /// Flag indicating if the tokenizer should strip accents (normalization). Only used for BERT / ALBERT models
+    pub strip_accents: Option<bool>,
+    /// Maximum number of tokens of a candidate span (default: 8)
+    pub max_span_width: usize,
+    /// Minimum probability of the entity label for a span to be returned (default: 0.5)
+    pub min_score: f64,
+    /// Device to place the model on (default: CUDA/GPU when available)
+    pub device: Device,
+}
+
+impl NestedNERConfig {
+    /// Instantiate a new nested NER configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * model - The `ResourceProvider` pointing to the model to load (e.g.  model.ot)
+    /// * config - The `ResourceProvider` pointing to the model configuration to load (e.g. config.json)
+    /// * vocab - The `ResourceProvider` pointing to the tokenizer's vocabulary to load (e.g.  vocab.txt)
+    /// * lower_case - A `bool` indicating whether the tokenizer should lower case all input (in case of a lower-cased model)
+    pub fn new<RM, RC, RV>(
+        model_resource: RM,
+        config_resource: RC,
+        vocab_resource: RV,
+        lower_case: bool,
+    ) -> NestedNERConfig
+    where
+        RM: ResourceProvider + Send + 'static,
+        RC: ResourceProvider + Send + 'static,
+        RV: ResourceProvider + Send + 'static,
+    {
+        NestedNERConfig {
+            model_resource: Box::new(model_resource),
+            config_resource: Box::new(config_resource),
+            vocab_resource: Box::new(vocab_resource),
+            lower_case,
+            strip_accents: None,
+            max_span_width: 8,
+            min_score: 0.5,
+            device: Device::cuda_if_available(),
+        }
+    }
+}
+
+/// # NestedNERModel to extract potentially overlapping named entities
+pub struct NestedNERModel {
+    tokenizer: TokenizerOption,
+    span_classifier: BertForSpanClassification,
+    label_mapping: HashMap<i64, String>,
+    null_label_id: i64,
+    max_span_width: usize,
+    min_score: f64,
+    max_length: usize,
+    var_store: VarStore,
+}
+
+impl NestedNERModel {
+    /// Build a new `NestedNERModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `NestedNERConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    pub fn new(config: NestedNERConfig) -> Result<NestedNERModel, RustBertError> {
+        let config_path = config.config_resource.get_local_path()?;
+        let vocab_path = config.vocab_resource.get_local_path()?;
+
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::Bert,
+            vocab_path.to_str().unwrap(),
+            None,
+            config.lower_case,
+            config.strip_accents,
+            None,
+        )?;
+        let bert_config = BertConfig::from_file(config_path);
+        let label_mapping = bert_config.id2label.clone().ok_or_else(|| {
+            RustBertError::InvalidConfigurationError(
+                "id2label must be provided for nested NER models".to_string(),
+            )
+        })?;
+        let null_label_id = label_mapping
+            .iter()
+            .find_map(|(id, label)| (label == "O").then_some(*id))
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "The labels of nested NER models must include \"O\" (no entity)".to_string(),
+                )
+            })?;
+        let max_length = bert_config.max_position_embeddings as usize;
+        if max_length < 3 {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "max_position_embeddings must leave room for the special tokens and at least one token, got {max_length}"
+            )));
+        }
+
+        let mut var_store = VarStore::new(config.device);
+        let span_classifier = BertForSpanClassification::new(var_store.root(), &bert_config)?;
+        load_weights(config.model_resource.as_ref(), &mut var_store)?;
+
+        Ok(NestedNERModel {
+            tokenizer,
+            span_classifier,
+            label_mapping,
+            null_label_id,
+            max_span_width: config.max_span_width,
+            min_score: config.min_score,
+            max_length,
+            var_store,
+        })
+    }
+
+    /// Get a reference to the mapping from label indices to label names
+    pub fn get_label_mapping(&self) -> &HashMap<i64, String> {
+        &self.label_mapping
+    }
+
+    /// Extract potentially overlapping entities from a text
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[S]` Array of texts to extract entities from (e.g. `&str` or `String`). Texts longer than the
+    /// maximum number of position embeddings of the model are truncated, and entities are only extracted from their
+    /// beginning (a warning is logged).
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Vec<Entity>>, RustBertError>` containing the extracted entities of each input, sorted by position
+    /// (entities starting at the same position are sorted from the longest to the shortest)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::nested_ner::{NestedNERConfig, NestedNERModel};
+    /// # use rust_bert::resources::LocalResource;
+    /// # use std::path::PathBuf;
+    /// # let config = NestedNERConfig::new(
+    /// #     LocalResource::from(PathBuf::from("path/to/rust_model.ot")),
+    /// #     LocalResource::from(PathBuf::from("path/to/config.json")),
+    /// #     LocalResource::from(PathBuf::from("path/to/vocab.txt")),
+    /// #     false,
+    /// # );
+    /// let nested_ner_model = NestedNERModel::new(config)?;
+    /// let input = [
+    ///     "He opened an account at the Bank of China.",
+    ///     "The University of California is based in Oakland.",
+    /// ];
+    /// let output = nested_ner_model.predict(&input)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict<S>(&self, input: &[S]) -> Result<Vec<Vec<Entity>>, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        input
+            .iter()
+            .map(|text| self.predict_single(text.as_ref()))
+            .collect()
+    }
+
+    fn predict_single(&self, input: &str) -> Result<Vec<Entity>, RustBertError> {
+        let tokenized_input = self.tokenizer.tokenize_with_offsets(input);
+        let mut encoded_input = TokenIdsWithOffsets {
+            ids: self
+                .tokenizer
+                .convert_tokens_to_ids(&tokenized_input.tokens),
+            offsets: tokenized_input.offsets,
+            reference_offsets: tokenized_input.reference_offsets,
+            masks: tokenized_input.masks,
+        };
+        let max_content_length = self.max_length - 2;
+        if encoded_input.ids.len() > max_content_length {
+            warn!(
+                "Input of {} tokens truncated to the {} tokens supported by the model",
+                encoded_input.ids.len(),
+                max_content_length
+            );
+            encoded_input.ids.truncate(max_content_length);
+            encoded_input.offsets.truncate(max_content_length);
+            encoded_input.reference_offsets.truncate(max_content_length);
+            encoded_input.masks.truncate(max_content_length);
+        }
+        let tokenized_input = self
+            .tokenizer
+            .build_input_with_special_tokens(encoded_input, None);
+
+        let spans = candidate_spans(
+            &tokenized_input.token_offsets,
+            &tokenized_input.mask,
+            self.max_span_width,
+        );
+        if spans.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let device = self.var_store.device();
+        let input_ids = Tensor::of_slice(&tokenized_input.token_ids)
+            .unsqueeze(0)
+            .to(device);
+        let span_starts = spans.iter().map(|span| span.0 as i64).collect::<Vec<i64>>();
+        let span_ends = spans.iter().map(|span| span.1 as i64).collect::<Vec<i64>>();
+        let span_starts = Tensor::of_slice(&span_starts).unsqueeze(0).to(device);
+        let span_ends = Tensor::of_slice(&span_ends).unsqueeze(0).to(device);
+
+        let (scores, labels) = no_grad(|| {
+            self.span_classifier
+                .forward_t(&input_ids, None, None, &span_starts, &span_ends, false)
+                .map(|output| output.logits.softmax(-1, Kind::Float).max_dim(-1, false))
+        })?;
+
+        let mut candidates = vec![];
+        for (span_index, (start, end)) in spans.into_iter().enumerate() {
+            let label_id = labels.int64_value(&[0, span_index as i64]);
+            let score = scores.double_value(&[0, span_index as i64]);
+            if (label_id == self.null_label_id) || (score < self.min_score) {
+                continue;
+            }
+            let label = match self.label_mapping.get(&label_id) {
+                Some(label) => label.clone(),
+                None => continue,
+            };
+            let offset = Offset {
+                begin: tokenized_input.token_offsets[start].unwrap().begin,
+                end: tokenized_input.token_offsets[end].unwrap().end,
+            };
+            candidates.push(Entity {
+                word: input
+                    .chars()
+                    .skip(offset.begin as usize)
+                    .take((offset.end - offset.begin) as usize)
+                    .collect(),
+                score,
+                label,
+                offset,
+                token_start: start,
+                token_end: end + 1,
+            });
+        }
+        Ok(remove_crossing_entities(candidates))
+    }
+}
+
+/// Enumerates the spans of up to `max_span_width` tokens starting at the beginning of a word and ending at
+/// the end of a word, as (first token, last token) positions. Special tokens are excluded.
+fn candidate_spans(
+    offsets: &[Option<Offset>],
+    masks: &[Mask],
+    max_span_width: usize,
+) -> Vec<(usize, usize)> {
+    let is_token =
+        |position: usize| offsets[position].is_some() && (masks[position] != Mask::Special);
+    let is_word_start =
+        |position: usize| is_token(position) && (masks[position] != Mask::Continuation);
+    let is_word_end = |position: usize| {
+        is_token(position)
+            && ((position + 1 == masks.len()) || (masks[position + 1] != Mask::Continuation))
+    };
+
+    let mut spans = vec![];
+    for start in (0..masks.len()).filter(|position| is_word_start(*position)) {
+        for end in start..min(start + max_span_width, masks.len()) {
+            if !is_token(end) {
+                break;
+            }
+            if is_word_end(end) {
+                spans.push((start, end));
+            }
+        }
+    }
+    spans
+}
+
+/// Keeps the highest scoring entity among partially overlapping entities. Entities nested in one another
+/// (one span containing the other) are all kept.
+fn remove_crossing_entities(mut candidates: Vec<Entity>) -> Vec<Entity> {
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let mut entities: Vec<Entity> = vec![];
+    for candidate in candidates {
+        let crosses = |entity: &Entity| {
+            let disjoint = (candidate.offset.end <= entity.offset.begin)
+                || (entity.offset.end <= candidate.offset.begin);
+            let nested = ((candidate.offset.begin >= entity.offset.begin)
+                && (candidate.offset.end <= entity.offset.end))
+                || ((entity.offset.begin >= candidate.offset.begin)
+                    && (entity.offset.end <= candidate.offset.end));
+            !disjoint && !nested
+        };
+        if !entities.iter().any(crosses) {
+            entities.push(candidate);
+        }
+    }
+    entities.sort_by_key(|entity| (entity.offset.begin, Reverse(entity.offset.end)));
+    entities
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entity(begin: u32, end: u32, score: f64, label: &str) -> Entity {
+        Entity {
+            word: String::new(),
+            score,
+            label: label.to_string(),
+            offset: Offset { begin, end },
+            token_start: 0,
+            token_end: 0,
+        }
+    }
+
+    #[test]
+    fn candidate_spans_respect_word_boundaries() {
+        //    [CLS] Bank of Ch ##ina [SEP]
+        let offsets = vec![
+            None,
+            Some(Offset { begin: 0, end: 4 }),
+            Some(Offset { begin: 5, end: 7 }),
+            Some(Offset { begin: 8, end: 10 }),
+            Some(Offset { begin: 10, end: 13 }),
+            None,
+        ];
+        let masks = vec![
+            Mask::Special,
+            Mask::None,
+            Mask::None,
+            Mask::Begin,
+            Mask::Continuation,
+            Mask::Special,
+        ];
+
+        let spans = candidate_spans(&offsets, &masks, 4);
+        assert_eq!(spans, vec![(1, 1), (1, 2), (1, 4), (2, 2), (2, 4), (3, 4)]);
+
+        let spans = candidate_spans(&offsets, &masks, 2);
+        assert_eq!(spans, vec![(1, 1), (1, 2), (2, 2), (3, 4)]);
+    }
+
+    #[test]
+    fn nested_entities_are_kept() {
+        let candidates = vec![
+            entity(8, 13, 0.9, "LOC"),
+            entity(0, 13, 0.95, "ORG"),
+            entity(5, 13, 0.6, "ORG"),
+            entity(0, 7, 0.7, "ORG"),
+        ];
+
+        let entities = remove_crossing_entities(candidates);
+
+        //    "of China" crosses the higher scoring "Bank of" and is removed
+        assert_eq!(entities.len(), 3);
+        assert_eq!(entities[0].offset, Offset { begin: 0, end: 13 });
+        assert_eq!(entities[1].offset, Offset { begin: 0, end: 7 });
+        assert_eq!(entities[2].offset, Offset { begin: 8, end: 13 });
+        assert_eq!(entities[2].label, "LOC");
+    }
+
+    #[test]
+    fn nan_scores_do_not_panic() {
+        let candidates = vec![entity(0, 13, f64::NAN, "ORG"), entity(8, 13, 0.9, "LOC")];
+
+        let entities = remove_crossing_entities(candidates);
+
+        assert_eq!(entities.len(), 2);
+    }
+}
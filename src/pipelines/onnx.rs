@@ -0,0 +1,184 @@
+// Copyright 2023 The rust-bert contributors
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # ONNX Runtime backend for token classification (requires the `onnx` feature)
+//! Runs the forward pass of token classification models (and therefore of the NER pipeline) exported to ONNX
+//! through [ONNX Runtime](https://onnxruntime.ai/). Tokenization and post-processing are identical to the
+//! Libtorch backend, and predictions are returned as the same `Token` and `Entity` structures.
+//!
+//! The ONNX backend is selected by pointing the `model_resource` of the `TokenClassificationConfig` (also used by the
+//! `NERModel`) to a file with the `.onnx` extension. The configuration and vocabulary resources are unchanged.
+//! Note that Libtorch remains a dependency of the crate: it is used for the post-processing of the model output.
+//! `ONNXTokenClassifier::forward` returns ONNX Runtime errors, but the prediction methods of the pipelines
+//! (e.g. `NERModel::predict`) panic on them, as for errors of the Libtorch forward pass.
+//!
+//! ## Exporting a model
+//! Token classification models can be exported from the Hugging Face Transformers checkpoint using
+//! [Optimum](https://huggingface.co/docs/optimum/exporters/onnx/usage_guides/export_a_model):
+//! ```bash
+//! optimum-cli export onnx --model dbmdz/bert-large-cased-finetuned-conll03-english --task token-classification bert-ner-onnx/
+//! ```
+//! The exported graph is expected to take `input_ids`, `attention_mask` and optionally `token_type_ids` inputs
+//! (64-bit integer tensors of shape (batch size, sequence length)) and to return the token classification logits
+//! of shape (batch size, sequence length, number of labels) as its first output.
+//! Models quantized with the ONNX Runtime dynamic quantization tools (int8 weights) keep these inputs and outputs,
+//! and can be used for faster CPU inference at the cost of a small accuracy loss.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::common::ModelType;
+//! use rust_bert::pipelines::ner::NERModel;
+//! use rust_bert::pipelines::token_classification::TokenClassificationConfig;
+//! use rust_bert::resources::LocalResource;
+//! use std::path::PathBuf;
+//!
+//! let config = TokenClassificationConfig {
+//!     model_type: ModelType::Bert,
+//!     model_resource: Box::new(LocalResource::from(PathBuf::from("bert-ner-onnx/model.onnx"))),
+//!     config_resource: Box::new(LocalResource::from(PathBuf::from("bert-ner-onnx/config.json"))),
+//!     vocab_resource: Box::new(LocalResource::from(PathBuf::from("bert-ner-onnx/vocab.txt"))),
+//!     ..Default::default()
+//! };
+//! let ner_model = NERModel::new(config)?;
+//! let output = ner_model.predict(&["My name is Amy. I live in Paris."]);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::common::ModelType;
+use ndarray::{ArrayD, CowArray, IxDyn};
+use ort::{Environment, GraphOptimizationLevel, Session, SessionBuilder, Value};
+use std::convert::TryFrom;
+use std::path::Path;
+use tch::{Device, Kind, Tensor};
+
+/// # Token classification model running on ONNX Runtime
+pub struct ONNXTokenClassifier {
+    session: Session,
+    input_names: Vec<String>,
+    model_type: ModelType,
+}
+
+impl ONNXTokenClassifier {
+    /// Load an ONNX token classification model from a file
+    ///
+    /// # Arguments
+    ///
+    /// * `model_path` - Path to the exported `.onnx` model
+    /// * `model_type` - `ModelType` of the exported model (used to select the tokenizer)
+    pub fn new<P: AsRef<Path>>(
+        model_path: P,
+        model_type: ModelType,
+    ) -> Result<ONNXTokenClassifier, RustBertError> {
+        let environment = Environment::builder()
+            .with_name("rust-bert")
+            .build()?
+            .into_arc();
+        let session = SessionBuilder::new(&environment)?
+            .with_optimization_level(GraphOptimizationLevel::Level3)?
+            .with_model_from_file(model_path)?;
+        let input_names = session
+            .inputs
+            .iter()
+            .map(|input| input.name.clone())
+            .collect();
+        Ok(ONNXTokenClassifier {
+            session,
+            input_names,
+            model_type,
+        })
+    }
+
+    /// Returns the `ModelType` of the exported model
+    pub fn model_type(&self) -> ModelType {
+        self.model_type
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Input tensor of shape (*batch size*, *sequence_length*)
+    /// * `attention_mask` - Mask tensor of shape (*batch size*, *sequence_length*)
+    /// * `token_type_ids` - Optional segment id tensor of shape (*batch size*, *sequence_length*), only fed to
+    /// models exported with a `token_type_ids` input (all zeros if not provided)
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*batch size*, *sequence_length*, *num_labels*) containing the logits for each token,
+    /// on the device of `input_ids`
+    pub fn forward(
+        &self,
+        input_ids: &Tensor,
+        attention_mask: &Tensor,
+        token_type_ids: Option<&Tensor>,
+    ) -> Result<Tensor, RustBertError> {
+        let mut input_arrays = Vec::with_capacity(self.input_names.len());
+        for input_name in &self.input_names {
+            let input = match input_name.as_str() {
+                "input_ids" => tensor_to_array(input_ids)?,
+                "attention_mask" => tensor_to_array(attention_mask)?,
+                "token_type_ids" => match token_type_ids {
+                    Some(token_type_ids) => tensor_to_array(token_type_ids)?,
+                    None => tensor_to_array(&input_ids.zeros_like())?,
+                },
+                _ => {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "Unsupported input `{input_name}` for the ONNX token classification model"
+                    )));
+                }
+            };
+            input_arrays.push(input);
+        }
+        let input_values = input_arrays
+            .iter()
+            .map(|input| Value::from_array(self.session.allocator(), input))
+            .collect::<Result<Vec<Value>, _>>()?;
+        let outputs = self.session.run(input_values)?;
+        let logits = outputs
+            .first()
+            .ok_or_else(|| {
+                RustBertError::ValueError("The ONNX model did not return any output".to_string())
+            })?
+            .try_extract::<f32>()?;
+        let logits = logits.view();
+        let shape = logits
+            .shape()
+            .iter()
+            .map(|dim| *dim as i64)
+            .collect::<Vec<i64>>();
+        let values = logits.as_standard_layout();
+        let values = values.as_slice().ok_or_else(|| {
+            RustBertError::ValueError("The ONNX model output is not contiguous".to_string())
+        })?;
+        Ok(Tensor::of_slice(values)
+            .view(shape.as_slice())
+            .to(input_ids.device()))
+    }
+}
+
+fn tensor_to_array(tensor: &Tensor) -> Result<CowArray<'static, i64, IxDyn>, RustBertError> {
+    let shape = tensor
+        .size()
+        .iter()
+        .map(|dim| *dim as usize)
+        .collect::<Vec<usize>>();
+    let values = Vec::<i64>::try_from(
+        &tensor
+            .to_device(Device::Cpu)
+            .to_kind(Kind::Int64)
+            .flatten(0, -1),
+    )?;
+    let array = ArrayD::from_shape_vec(IxDyn(&shape), values)
+        .map_err(|error| RustBertError::ValueError(error.to_string()))?;
+    Ok(CowArray::from(array))
+}
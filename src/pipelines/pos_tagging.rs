@@ -136,6 +136,15 @@ impl Default for POSConfig {
                 device: Device::cuda_if_available(),
                 label_aggregation_function: LabelAggregationOption::First,
                 batch_size: 64,
+                max_length: None,
+                tokenization_cache_size: None,
+                window_overlap: None,
+                output_hidden_states: false,
+                output_attentions: false,
+                seed: None,
+                log_probabilities: false,
+                labels_resource: None,
+                add_special_tokens: true,
             },
         }
     }
@@ -186,6 +195,11 @@ impl POSModel {
     ///
     /// * `Vec<Vec<POSTag>>` containing Part of Speech tags for the inputs provided
     ///
+    /// # Panics
+    ///
+    /// Panics if the forward pass of the model fails, for example when ONNX Runtime rejects the inputs of a model
+    /// run with the `onnx` feature. The other prediction methods share this behaviour.
+    ///
     /// # Example
     ///
     /// ```no_run
@@ -205,6 +219,11 @@ impl POSModel {
     where
         S: AsRef<str>,
     {
+        let (min_score, max_score) = if self.token_classification_model.log_probabilities() {
+            (0.5f64.ln(), 0f64)
+        } else {
+            (0.5f64, 1f64)
+        };
         self.token_classification_model
             .predict(input, true, false)
             .into_iter()
@@ -213,10 +232,10 @@ impl POSModel {
                     .into_iter()
                     .map(|mut token| {
                         if (Self::is_punctuation(token.text.as_str()))
-                            & ((token.score < 0.5) | token.score.is_nan())
+                            & ((token.score < min_score) | token.score.is_nan())
                         {
                             token.label = String::from(".");
-                            token.score = 1f64;
+                            token.score = max_score;
                         };
                         token
                     })
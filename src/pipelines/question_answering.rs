@@ -16,6 +16,9 @@
 //! Customized DistilBERT models can be loaded by overwriting the resources in the configuration.
 //! The dependencies will be downloaded to the user's home directory, under ~/.cache/.rustbert/distilbert-qa
 //!
+//! Question and context are encoded jointly (separated by the tokenizer separator token). Positions of the question
+//! segment and of special tokens are masked before decoding, so that answer spans are always extracted from the context.
+//!
 //! ```no_run
 //! use rust_bert::pipelines::question_answering::{QaInput, QuestionAnsweringModel};
 //!
@@ -23,6 +23,47 @@ pub struct PoolingConfig {
 
 impl Config for PoolingConfig {}
 
+/// # Pooling strategy collapsing token embeddings into a single sentence embedding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PoolingStrategy {
+    /// Use the embedding of the first token (`[CLS]`)
+    Cls,
+    /// Average the embeddings of all non-padding tokens (used by most sentence-transformers models,
+    /// e.g. `all-MiniLM-L12-v2` or `bert-base-nli-mean-tokens`)
+    #[default]
+    Mean,
+    /// Take the maximum over all non-padding tokens in each dimension
+    Max,
+}
+
+impl PoolingConfig {
+    /// Creates a pooling configuration using a single pooling strategy
+    ///
+    /// # Arguments
+    ///
+    /// * `word_embedding_dimension` - Dimension of the token embeddings
+    /// * `pooling_strategy` - `PoolingStrategy` to apply
+    pub fn new(word_embedding_dimension: i64, pooling_strategy: PoolingStrategy) -> PoolingConfig {
+        let mut pooling_config = PoolingConfig {
+            word_embedding_dimension,
+            pooling_mode_cls_token: false,
+            pooling_mode_max_tokens: false,
+            pooling_mode_mean_tokens: false,
+            pooling_mode_mean_sqrt_len_tokens: false,
+        };
+        pooling_config.set_pooling_strategy(pooling_strategy);
+        pooling_config
+    }
+
+    /// Replaces the pooling modes of the configuration with a single pooling strategy
+    pub fn set_pooling_strategy(&mut self, pooling_strategy: PoolingStrategy) {
+        self.pooling_mode_cls_token = pooling_strategy == PoolingStrategy::Cls;
+        self.pooling_mode_max_tokens = pooling_strategy == PoolingStrategy::Max;
+        self.pooling_mode_mean_tokens = pooling_strategy == PoolingStrategy::Mean;
+        self.pooling_mode_mean_sqrt_len_tokens = false;
+    }
+}
+
 /// Performs pooling (max or mean) on the token embeddings.
 ///
 /// Using pooling, it generates from a variable sized sentence a fixed sized sentence
@@ -36,6 +77,11 @@ impl Pooling {
         Pooling { conf }
     }
 
+    /// Replaces the pooling modes of the layer with a single pooling strategy
+    pub fn set_pooling_strategy(&mut self, pooling_strategy: PoolingStrategy) {
+        self.conf.set_pooling_strategy(pooling_strategy);
+    }
+
     pub fn forward(&self, mut token_embeddings: Tensor, attention_mask: &Tensor) -> Tensor {
         let mut output_vectors = Vec::new();
 
@@ -48,7 +94,7 @@ impl Pooling {
             let input_mask_expanded = attention_mask.unsqueeze(-1).expand_as(&token_embeddings);
             // Set padding tokens to large negative value
             token_embeddings = token_embeddings.masked_fill_(&input_mask_expanded.eq(0), -1e9);
-            let max_over_time = token_embeddings.max_dim(1, true).0;
+            let max_over_time = token_embeddings.max_dim(1, false).0;
             output_vectors.push(max_over_time);
         }
 
@@ -151,3 +197,26 @@ impl Dense {
         self.activation.get_fn()(&x.apply(&self.linear))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pooling_strategies_ignore_padding() {
+        let token_embeddings = Tensor::of_slice(&[1f32, 4., 3., 2., 100., 100.]).view((1, 3, 2));
+        let attention_mask = Tensor::of_slice(&[1i64, 1, 0]).view((1, 3));
+        let pool = |pooling_strategy| {
+            Pooling::new(PoolingConfig::new(2, pooling_strategy))
+                .forward(token_embeddings.copy(), &attention_mask)
+                .view(-1)
+                .iter::<f64>()
+                .unwrap()
+                .collect::<Vec<f64>>()
+        };
+
+        assert_eq!(pool(PoolingStrategy::Cls), vec![1., 4.]);
+        assert_eq!(pool(PoolingStrategy::Mean), vec![2., 3.]);
+        assert_eq!(pool(PoolingStrategy::Max), vec![3., 4.]);
+    }
+}
@@ -9,6 +9,11 @@
 //! convert them using the script `utils/convert_model.py` beforehand, see
 //! `tests/sentence_embeddings.rs` for such examples.
 //!
+//! The pipeline can also be used for feature extraction with a frozen encoder (e.g. to cluster texts):
+//! the transformer backbone runs without any task-specific head and the token embeddings are pooled
+//! into a single vector per input (using the `[CLS]` token, mean or max pooling as defined by the
+//! pooling layer configuration). `encode` returns one `Vec<f32>` embedding per input.
+//!
 //! [sbert]: https://sbert.net/
 //! [sbert-hub]: https://huggingface.co/sentence-transformers/
 //!
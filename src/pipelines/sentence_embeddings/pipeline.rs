@@ -8,7 +8,9 @@ use crate::albert::AlbertForSentenceEmbeddings;
 use crate::bert::BertForSentenceEmbeddings;
 use crate::distilbert::DistilBertForSentenceEmbeddings;
 use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
-use crate::pipelines::sentence_embeddings::layers::{Dense, DenseConfig, Pooling, PoolingConfig};
+use crate::pipelines::sentence_embeddings::layers::{
+    Dense, DenseConfig, Pooling, PoolingConfig, PoolingStrategy,
+};
 use crate::pipelines::sentence_embeddings::{
     AttentionHead, AttentionLayer, AttentionOutput, Embedding, SentenceEmbeddingsConfig,
     SentenceEmbeddingsModulesConfig, SentenceEmbeddingsSentenceBertConfig,
@@ -267,6 +269,11 @@ impl SentenceEmbeddingsModel {
         })
     }
 
+    /// Sets the pooling strategy, replacing the pooling modes defined by the pooling layer configuration
+    pub fn set_pooling_strategy(&mut self, pooling_strategy: PoolingStrategy) {
+        self.pooling_layer.set_pooling_strategy(pooling_strategy);
+    }
+
     /// Sets the tokenizer's truncation strategy
     pub fn set_tokenizer_truncation(&mut self, truncation_strategy: TruncationStrategy) {
         self.tokenizer_truncation_strategy = truncation_strategy;
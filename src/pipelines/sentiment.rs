@@ -26,7 +26,7 @@
 //!     "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
 //!     "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
 //! ];
-//! let output = sentiment_classifier.predict(&input);
+//! let output = sentiment_classifier.predict(&input)?;
 //! # Ok(())
 //! # }
 //! ```
@@ -111,10 +111,11 @@ impl SentimentModel {
     ///
     /// # Arguments
     ///
-    /// * `input` - `&[&str]` Array of texts to extract the sentiment from.
+    /// * `input` - `&[S]` Array of texts to extract the sentiment from (e.g. `&str` or `String`).
     ///
     /// # Returns
-    /// * `Vec<Sentiment>` Sentiments extracted from texts.
+    /// * `Result<Vec<Sentiment>, RustBertError>` Sentiments extracted from texts. An error is returned if an input
+    /// exceeds the maximum length of the model and truncation is disabled (see `SequenceClassificationModel::predict`).
     ///
     /// # Example
     ///
@@ -130,15 +131,15 @@ impl SentimentModel {
     ///     "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
     /// ];
     ///
-    /// let output = sentiment_classifier.predict(&input);
+    /// let output = sentiment_classifier.predict(&input)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn predict<'a, S>(&self, input: S) -> Vec<Sentiment>
+    pub fn predict<S>(&self, input: &[S]) -> Result<Vec<Sentiment>, RustBertError>
     where
-        S: AsRef<[&'a str]>,
+        S: AsRef<str> + Sync,
     {
-        let labels = self.sequence_classification_model.predict(input);
+        let labels = self.sequence_classification_model.predict(input)?;
         let mut sentiments = Vec::with_capacity(labels.len());
         for label in labels {
             let polarity = if label.id == 1 {
@@ -151,7 +152,7 @@ impl SentimentModel {
                 score: label.score,
             })
         }
-        sentiments
+        Ok(sentiments)
     }
 }
 #[cfg(test)]
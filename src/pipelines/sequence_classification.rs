@@ -12,6 +12,12 @@
 // limitations under the License.
 //! # Sequence classification pipeline (e.g. Sentiment Analysis)
 //! More generic sequence classification pipeline, works with multiple models (Bert, Roberta)
+//! and serves tasks such as sentiment, topic or intent classification. The model is constructed in the same way as
+//! the token classification and NER pipelines, and label names are read from the `id2label` mapping of the model configuration.
+//! `predict` returns the top label and its score for each input.
+//! For classification against arbitrary candidate labels without fine-tuning, see the
+//! [zero-shot classification pipeline](../zero_shot_classification/index.html), which relies on a model fine-tuned
+//! on MNLI (BART, BERT, RoBERTa, ...) and scores templated hypotheses against each input.
 //!
 //! ```no_run
 //! use rust_bert::pipelines::sequence_classification::SequenceClassificationConfig;
@@ -40,7 +46,7 @@
 //!     "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
 //!     "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
 //! ];
-//! let output = sequence_classification_model.predict(&input);
+//! let output = sequence_classification_model.predict(&input)?;
 //! # Ok(())
 //! # }
 //! ```
@@ -53,7 +59,7 @@
 //! [
 //!    Label { text: String::from("POSITIVE"), score: 0.9986, id: 1, sentence: 0},
 //!    Label { text: String::from("NEGATIVE"), score: 0.9985, id: 0, sentence: 1},
-//!    Label { text: String::from("POSITIVE"), score: 0.9988, id: 1, sentence: 12},
+//!    Label { text: String::from("POSITIVE"), score: 0.9988, id: 1, sentence: 2},
 //! ]
 //! # ;
 //! ```
@@ -68,13 +74,14 @@ use crate::longformer::LongformerForSequenceClassification;
 use crate::mobilebert::MobileBertForSequenceClassification;
 use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
 use crate::reformer::ReformerForSequenceClassification;
-use crate::resources::ResourceProvider;
+use crate::resources::{load_weights, ResourceProvider};
 use crate::roberta::RobertaForSequenceClassification;
 use crate::xlnet::XLNetForSequenceClassification;
 use rust_tokenizers::tokenizer::TruncationStrategy;
 use rust_tokenizers::TokenizedInput;
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use tch::nn::VarStore;
 use tch::{nn, no_grad, Device, Kind, Tensor};
@@ -121,6 +128,10 @@ pub struct SequenceClassificationConfig {
     pub add_prefix_space: Option<bool>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Truncation strategy applied to inputs longer than the maximum length of the model (default: `LongestFirst`).
+    /// `DoNotTruncate` causes over-length inputs to be rejected instead. `OnlySecond` is only valid for
+    /// sequence pairs (`predict_pairs`).
+    pub truncation_strategy: TruncationStrategy,
 }
 
 impl SequenceClassificationConfig {
@@ -159,6 +170,7 @@ impl SequenceClassificationConfig {
             strip_accents: strip_accents.into(),
             add_prefix_space: add_prefix_space.into(),
             device: Device::cuda_if_available(),
+            truncation_strategy: TruncationStrategy::LongestFirst,
         }
     }
 }
@@ -545,6 +557,7 @@ pub struct SequenceClassificationModel {
     label_mapping: HashMap<i64, String>,
     var_store: VarStore,
     max_length: usize,
+    truncation_strategy: TruncationStrategy,
 }
 
 impl SequenceClassificationModel {
@@ -569,14 +582,12 @@ impl SequenceClassificationModel {
     ) -> Result<SequenceClassificationModel, RustBertError> {
         let config_path = config.config_resource.get_local_path()?;
         let vocab_path = config.vocab_resource.get_local_path()?;
-        let weights_path = config.model_resource.get_local_path()?;
         let merges_path = if let Some(merges_resource) = &config.merges_resource {
             Some(merges_resource.get_local_path()?)
         } else {
             None
         };
         let device = config.device;
-
         let tokenizer = TokenizerOption::from_file(
             config.model_type,
             vocab_path.to_str().unwrap(),
@@ -593,27 +604,87 @@ impl SequenceClassificationModel {
             .unwrap_or(usize::MAX);
         let sequence_classifier =
             SequenceClassificationOption::new(config.model_type, var_store.root(), &model_config)?;
-        let label_mapping = model_config.get_label_mapping().clone();
-        var_store.load(weights_path)?;
+        let label_mapping = model_config.get_label_mapping()?.clone();
+        load_weights(config.model_resource.as_ref(), &mut var_store)?;
         Ok(SequenceClassificationModel {
             tokenizer,
             sequence_classifier,
             label_mapping,
             var_store,
             max_length,
+            truncation_strategy: config.truncation_strategy,
         })
     }
 
-    fn prepare_for_model<'a, S>(&self, input: S) -> Tensor
+    fn prepare_for_model<S>(&self, input: &[S]) -> Result<(Tensor, Tensor), RustBertError>
     where
-        S: AsRef<[&'a str]>,
+        S: AsRef<str> + Sync,
     {
+        if matches!(self.truncation_strategy, TruncationStrategy::OnlySecond) {
+            return Err(RustBertError::InvalidConfigurationError(
+                "The OnlySecond truncation strategy requires sequence pairs".to_string(),
+            ));
+        }
         let tokenized_input: Vec<TokenizedInput> = self.tokenizer.encode_list(
-            input.as_ref(),
-            self.max_length,
-            &TruncationStrategy::LongestFirst,
+            input,
+            self.encoding_max_length(),
+            &self.truncation_strategy,
+            0,
+        );
+        self.input_tensors(&tokenized_input)
+    }
+
+    fn prepare_pairs_for_model(
+        &self,
+        input: &[(&str, &str)],
+    ) -> Result<(Tensor, Tensor, Tensor), RustBertError> {
+        let tokenized_input: Vec<TokenizedInput> = self.tokenizer.encode_pair_list(
+            input,
+            self.encoding_max_length(),
+            &self.truncation_strategy,
             0,
         );
+        let (input_tensor, mask) = self.input_tensors(&tokenized_input)?;
+        let max_len = input_tensor.size()[1] as usize;
+        let mut token_type_ids = Vec::with_capacity(tokenized_input.len() * max_len);
+        for input in tokenized_input.iter() {
+            token_type_ids.extend(
+                input
+                    .segment_ids
+                    .iter()
+                    .map(|segment_id| *segment_id as i64),
+            );
+            token_type_ids.resize(token_type_ids.len() + max_len - input.segment_ids.len(), 0);
+        }
+        let token_type_ids = Tensor::of_slice(&token_type_ids)
+            .view((tokenized_input.len() as i64, max_len as i64))
+            .to(self.var_store.device());
+        Ok((input_tensor, mask, token_type_ids))
+    }
+
+    fn encoding_max_length(&self) -> usize {
+        match self.truncation_strategy {
+            TruncationStrategy::DoNotTruncate => usize::MAX,
+            _ => self.max_length,
+        }
+    }
+
+    /// Builds the padded input ids, attention mask and token type ids tensors
+    fn input_tensors(
+        &self,
+        tokenized_input: &[TokenizedInput],
+    ) -> Result<(Tensor, Tensor), RustBertError> {
+        if let Some((input_index, too_long)) = tokenized_input
+            .iter()
+            .enumerate()
+            .find(|(_, input)| input.token_ids.len() > self.max_length)
+        {
+            return Err(RustBertError::ValueError(format!(
+                "Input {input_index} is {} tokens long, exceeding the maximum length of the model ({}) while truncation is disabled",
+                too_long.token_ids.len(),
+                self.max_length
+            )));
+        }
         let max_len = tokenized_input
             .iter()
             .map(|input| input.token_ids.len())
@@ -623,25 +694,30 @@ impl SequenceClassificationModel {
             .tokenizer
             .get_pad_id()
             .expect("The Tokenizer used for sequence classification should contain a PAD id");
-        let tokenized_input_tensors: Vec<tch::Tensor> = tokenized_input
-            .into_iter()
-            .map(|mut input| {
-                input.token_ids.resize(max_len, pad_id);
-                Tensor::of_slice(&(input.token_ids))
-            })
-            .collect::<Vec<_>>();
-        Tensor::stack(tokenized_input_tensors.as_slice(), 0).to(self.var_store.device())
+        // The padded input ids are written to a single vector and copied to a tensor at once
+        let mut input_ids = Vec::with_capacity(tokenized_input.len() * max_len);
+        for input in tokenized_input.iter() {
+            input_ids.extend_from_slice(&input.token_ids);
+            input_ids.resize(input_ids.len() + max_len - input.token_ids.len(), pad_id);
+        }
+        let input_tensor = Tensor::of_slice(input_ids.as_slice())
+            .view((tokenized_input.len() as i64, max_len as i64))
+            .to(self.var_store.device());
+        let mask = input_tensor.ne(pad_id).to_kind(Kind::Bool);
+        Ok((input_tensor, mask))
     }
 
     /// Classify texts
     ///
     /// # Arguments
     ///
-    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `input` - `&[S]` Array of texts to classify (e.g. `&str` or `String`).
     ///
     /// # Returns
     ///
-    /// * `Vec<Label>` containing labels for input texts
+    /// * `Result<Vec<Label>, RustBertError>` containing labels for input texts. An error is returned if an input
+    /// exceeds the maximum length of the model and truncation is disabled, or if the model was configured with
+    /// `TruncationStrategy::OnlySecond` (only valid for pairs).
     ///
     /// # Example
     ///
@@ -655,26 +731,68 @@ impl SequenceClassificationModel {
     ///     "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
     ///     "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
     /// ];
-    /// let output = sequence_classification_model.predict(&input);
+    /// let output = sequence_classification_model.predict(&input)?;
     /// # Ok(())
     /// # }
     /// ```
-    pub fn predict<'a, S>(&self, input: S) -> Vec<Label>
+    pub fn predict<S>(&self, input: &[S]) -> Result<Vec<Label>, RustBertError>
     where
-        S: AsRef<[&'a str]>,
+        S: AsRef<str> + Sync,
     {
-        let input_tensor = self.prepare_for_model(input.as_ref());
-        let output = no_grad(|| {
-            let output = self.sequence_classifier.forward_t(
-                Some(&input_tensor),
-                None,
-                None,
-                None,
-                None,
-                false,
-            );
-            output.softmax(-1, Kind::Float).detach().to(Device::Cpu)
-        });
+        if input.is_empty() {
+            return Ok(vec![]);
+        }
+        let (input_tensor, mask) = self.prepare_for_model(input)?;
+        Ok(self.top_labels(&input_tensor, &mask, None))
+    }
+
+    /// Classify pairs of texts (e.g. premise and hypothesis for natural language inference, or paraphrase detection).
+    /// Each pair is encoded as a single sequence with segment (token type) ids distinguishing the two texts.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[(&str, &str)]` Array of text pairs to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Label>, RustBertError>` containing labels for each input pair. An error is returned if an input
+    /// exceeds the maximum length of the model and truncation is disabled.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let input = [(
+    ///     "A soccer game with multiple males playing.",
+    ///     "Some men are playing a sport.",
+    /// )];
+    /// let output = sequence_classification_model.predict_pairs(&input)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_pairs(&self, input: &[(&str, &str)]) -> Result<Vec<Label>, RustBertError> {
+        if input.is_empty() {
+            return Ok(vec![]);
+        }
+        let (input_tensor, mask, token_type_ids) = self.prepare_pairs_for_model(input)?;
+        // Only pass segment ids to models trained with token type embeddings for pairs
+        let token_type_ids = match self.sequence_classifier.model_type() {
+            ModelType::Bert | ModelType::Albert | ModelType::XLNet => Some(&token_type_ids),
+            _ => None,
+        };
+        Ok(self.top_labels(&input_tensor, &mask, token_type_ids))
+    }
+
+    fn top_labels(
+        &self,
+        input_tensor: &Tensor,
+        mask: &Tensor,
+        token_type_ids: Option<&Tensor>,
+    ) -> Vec<Label> {
+        let output = self.label_probabilities(input_tensor, mask, token_type_ids);
         let label_indices = output.as_ref().argmax(-1, true).squeeze_dim(1);
         let scores = output
             .gather(1, &label_indices.unsqueeze(-1), false)
@@ -700,11 +818,97 @@ impl SequenceClassificationModel {
         labels
     }
 
+    /// Runs the model and returns the probabilities of each label, of shape (*batch size*, *num labels*), on CPU
+    fn label_probabilities(
+        &self,
+        input_tensor: &Tensor,
+        mask: &Tensor,
+        token_type_ids: Option<&Tensor>,
+    ) -> Tensor {
+        no_grad(|| {
+            let output = self.sequence_classifier.forward_t(
+                Some(input_tensor),
+                Some(mask),
+                token_type_ids,
+                None,
+                None,
+                false,
+            );
+            output.softmax(-1, Kind::Float).detach().to(Device::Cpu)
+        })
+    }
+
+    /// Classify texts, returning the `k` most likely labels for each input
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[S]` Array of texts to classify (e.g. `&str` or `String`).
+    /// * `k` - `usize` number of labels to return for each input (capped to the number of labels of the model)
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Vec<Label>>, RustBertError>` containing the top `k` labels for each input text, sorted by descending score.
+    /// Ties are broken by ascending label id. An error is returned if an input exceeds the maximum length of the model
+    /// and truncation is disabled, or if a label index is missing from the label mapping of the model.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let sequence_classification_model =  SequenceClassificationModel::new(Default::default())?;
+    /// let input = [
+    ///     "Probably my all-time favorite movie, a story of selflessness, sacrifice and dedication to a noble cause, but it's not preachy or boring.",
+    ///     "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
+    /// ];
+    /// let output = sequence_classification_model.predict_topk(&input, 2)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_topk<S>(&self, input: &[S], k: usize) -> Result<Vec<Vec<Label>>, RustBertError>
+    where
+        S: AsRef<str> + Sync,
+    {
+        if input.is_empty() {
+            return Ok(vec![]);
+        }
+        let (input_tensor, mask) = self.prepare_for_model(input)?;
+        let output = self.label_probabilities(&input_tensor, &mask, None);
+
+        let mut labels: Vec<Vec<Label>> = Vec::with_capacity(output.size()[0] as usize);
+        for sentence_idx in 0..output.size()[0] {
+            let scores = output
+                .get(sentence_idx)
+                .iter::<f64>()
+                .unwrap()
+                .collect::<Vec<f64>>();
+            let sequence_labels = top_k_scores(&scores, k)
+                .into_iter()
+                .map(|(id, score)| {
+                    let text = self.label_mapping.get(&id).cloned().ok_or_else(|| {
+                        RustBertError::InvalidConfigurationError(format!(
+                            "Label index {id} is missing from the label mapping of the model"
+                        ))
+                    })?;
+                    Ok(Label {
+                        text,
+                        score,
+                        id,
+                        sentence: sentence_idx as usize,
+                    })
+                })
+                .collect::<Result<Vec<Label>, RustBertError>>()?;
+            labels.push(sequence_labels);
+        }
+        Ok(labels)
+    }
+
     /// Multi-label classification of texts
     ///
     /// # Arguments
     ///
-    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `input` - `&[S]` Array of texts to classify (e.g. `&str` or `String`).
     /// * `threshold` - `f64` threshold above which a label will be considered true by the classifier
     ///
     /// # Returns
@@ -727,16 +931,22 @@ impl SequenceClassificationModel {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn predict_multilabel(
+    pub fn predict_multilabel<S>(
         &self,
-        input: &[&str],
+        input: &[S],
         threshold: f64,
-    ) -> Result<Vec<Vec<Label>>, RustBertError> {
-        let input_tensor = self.prepare_for_model(input);
+    ) -> Result<Vec<Vec<Label>>, RustBertError>
+    where
+        S: AsRef<str> + Sync,
+    {
+        if input.is_empty() {
+            return Ok(vec![]);
+        }
+        let (input_tensor, mask) = self.prepare_for_model(input)?;
         let output = no_grad(|| {
             let output = self.sequence_classifier.forward_t(
                 Some(&input_tensor),
-                None,
+                Some(&mask),
                 None,
                 None,
                 None,
@@ -777,6 +987,23 @@ impl SequenceClassificationModel {
     }
 }
 
+/// Returns the `k` highest `(label id, score)` pairs, sorted by descending score and ascending label id
+fn top_k_scores(scores: &[f64], k: usize) -> Vec<(i64, f64)> {
+    let mut indexed_scores = scores
+        .iter()
+        .enumerate()
+        .map(|(id, score)| (id as i64, *score))
+        .collect::<Vec<(i64, f64)>>();
+    indexed_scores.sort_by(|(id_a, score_a), (id_b, score_b)| {
+        score_b
+            .partial_cmp(score_a)
+            .unwrap_or(Ordering::Equal)
+            .then(id_a.cmp(id_b))
+    });
+    indexed_scores.truncate(k);
+    indexed_scores
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -787,4 +1014,13 @@ mod test {
         let config = SequenceClassificationConfig::default();
         let _: Box<dyn Send> = Box::new(SequenceClassificationModel::new(config));
     }
+
+    #[test]
+    fn top_k_scores_sorted_with_ties() {
+        let scores = [0.1, 0.3, 0.2, 0.3, 0.1];
+
+        assert_eq!(top_k_scores(&scores, 3), vec![(1, 0.3), (3, 0.3), (2, 0.2)]);
+        assert_eq!(top_k_scores(&scores, 10).len(), 5);
+        assert!(top_k_scores(&scores, 0).is_empty());
+    }
 }
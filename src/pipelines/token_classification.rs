@@ -12,7 +12,9 @@
 // limitations under the License.
 
 //! # Token classification pipeline (Named Entity Recognition, Part-of-Speech tagging)
-//! More generic token classification pipeline, works with multiple models (Bert, Roberta)
+//! More generic token classification pipeline, works with multiple models (BERT, DeBERTa, DeBERTa V2, DistilBERT,
+//! MobileBERT, RoBERTa, XLM-RoBERTa, Electra, ALBERT, XLNet, Longformer and FNet). The architecture is selected using the
+//! `model_type` of the `TokenClassificationConfig`, lighter models such as DistilBERT may be used for lower latency.
 //!
 //! ```no_run
 //! use rust_bert::pipelines::token_classification::{TokenClassificationModel,TokenClassificationConfig};
@@ -59,6 +61,7 @@
 //!         label_index: 0,
 //!         sentence: 0,
 //!         index: 0,
+//!         index_end: 1,
 //!         word_index: 0,
 //!         offset: None,
 //!         mask: Mask::Special,
@@ -70,6 +73,7 @@
 //!         label_index: 0,
 //!         sentence: 0,
 //!         index: 1,
+//!         index_end: 2,
 //!         word_index: 1,
 //!         offset: Some(Offset { begin: 0, end: 2 }),
 //!         mask: Mask::None,
@@ -81,6 +85,7 @@
 //!         label_index: 0,
 //!         sentence: 0,
 //!         index: 2,
+//!         index_end: 3,
 //!         word_index: 2,
 //!         offset: Some(Offset { begin: 3, end: 7 }),
 //!         mask: Mask::None,
@@ -92,6 +97,7 @@
 //!         label_index: 0,
 //!         sentence: 0,
 //!         index: 3,
+//!         index_end: 4,
 //!         word_index: 3,
 //!         offset: Some(Offset { begin: 8, end: 10 }),
 //!         mask: Mask::None,
@@ -103,6 +109,7 @@
 //!         label_index: 4,
 //!         sentence: 0,
 //!         index: 4,
+//!         index_end: 5,
 //!         word_index: 4,
 //!         offset: Some(Offset { begin: 11, end: 17 }),
 //!         mask: Mask::None,
@@ -110,6 +117,24 @@
 //! ]
 //! # ;
 //! ```
+//!
+//! ## Reproducibility
+//! Predictions do not involve random sampling (dropout is disabled at inference), and are deterministic on CPU.
+//! On GPU, scores for identical inputs may differ slightly between runs because of the kernels selected by cuDNN
+//! and of reductions relying on atomic operations. Setting the `seed` of the `TokenClassificationConfig` seeds
+//! the Libtorch random number generators and disables cuDNN benchmarking, for the whole process. The `tch` bindings
+//! do not expose Libtorch's deterministic algorithms switch (`torch.use_deterministic_algorithms` in Python), so
+//! operations without a deterministic implementation are not reported and may still vary between runs on GPU.
+//! cuBLAS matrix multiplications additionally require the `CUBLAS_WORKSPACE_CONFIG=:4096:8` environment variable
+//! to be set before the process starts to be deterministic. Scores computed in half precision (see `TokenClassificationModel::half`) or with different batch
+//! compositions (padding lengths) may also differ within numerical precision.
+//!
+//! ## Logging
+//! The pipeline reports its processing through the [`log`](https://docs.rs/log) crate: the device the model is
+//! loaded on and the number of batches at the `debug` level, the inputs split into several windows because they
+//! exceed `max_length` at the `debug` level, the shape of each batch at the `trace` level and tokens skipped because
+//! of a label missing from the label mapping at the `warn` level. Messages are only emitted if a logger
+//! implementation (e.g. `env_logger`) is installed by the application.
 
 use crate::albert::AlbertForTokenClassification;
 use crate::bert::BertForTokenClassification;
@@ -121,21 +146,30 @@ use crate::fnet::FNetForTokenClassification;
 use crate::longformer::LongformerForTokenClassification;
 use crate::mobilebert::MobileBertForTokenClassification;
 use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
-use crate::resources::ResourceProvider;
+#[cfg(feature = "onnx")]
+use crate::pipelines::onnx::ONNXTokenClassifier;
+#[cfg(feature = "onnx")]
+use crate::resources::Resource;
+use crate::resources::{load_weights, weight_shape, LocalResource, ResourceProvider};
 use crate::roberta::RobertaForTokenClassification;
 use crate::xlnet::XLNetForTokenClassification;
+use crate::Config;
+use log::{debug, trace, warn};
 use ordered_float::OrderedFloat;
 use rust_tokenizers::tokenizer::Tokenizer;
 use rust_tokenizers::{
     ConsolidatableTokens, ConsolidatedTokenIterator, Mask, Offset, TokenIdsWithOffsets, TokenTrait,
-    TokenizedInput,
 };
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
 use std::cmp::min;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tch::nn::VarStore;
-use tch::{nn, no_grad, Device, Kind, Tensor};
+use tch::{nn, no_grad, Device, Kind, Reduction, Tensor};
 
 use crate::deberta_v2::DebertaV2ForTokenClassification;
 #[cfg(feature = "remote")]
@@ -155,10 +189,12 @@ pub struct Token {
     pub label: String,
     /// Label index
     pub label_index: i64,
-    /// Sentence index
+    /// Sentence index (position of the originating text in the input slice)
     pub sentence: usize,
     /// Token position index
     pub index: u16,
+    /// Position index following the last sub-token of this token (equal to `index + 1` unless sub-tokens were consolidated)
+    pub index_end: u16,
     /// Token word position index
     pub word_index: u16,
     /// Token offsets
@@ -167,6 +203,65 @@ pub struct Token {
     pub mask: Mask,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Token with the probability of every label, generated by a `TokenClassificationModel`
+pub struct TokenLabelScores {
+    /// Token with its most likely label
+    pub token: Token,
+    /// Probability for each label of the model, indexed by label id
+    pub label_scores: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Token with the raw (unnormalized) logit of every label, generated by a `TokenClassificationModel`
+pub struct TokenLabelLogits {
+    /// Token with its most likely label
+    pub token: Token,
+    /// Logit for each label of the model, indexed by label id
+    pub label_logits: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Token with the entropy of its label distribution, generated by a `TokenClassificationModel`
+pub struct TokenEntropy {
+    /// Token with its most likely label
+    pub token: Token,
+    /// Shannon entropy (in nats) of the label probability distribution of the token. Ranges from 0 (the model
+    /// is certain of the label) to the logarithm of the number of labels (uniform distribution).
+    pub entropy: f64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// # Time spent in each stage of a prediction, returned by the `predict_with_timings` methods
+pub struct Timings {
+    /// Tokenization of the inputs and generation of the model features
+    pub tokenize: Duration,
+    /// Padding of the batches and forward pass of the model
+    pub forward: Duration,
+    /// Decoding of the model output into labelled tokens (and, if requested, consolidation of sub-tokens or entities)
+    pub postprocess: Duration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Tokenization of an input as seen by a `TokenClassificationModel`, returned by `tokenize_debug`
+pub struct TokenizationDebug {
+    /// Text of the word pieces of the input (excluding special tokens)
+    pub tokens: Vec<String>,
+    /// Vocabulary ids of the word pieces
+    pub token_ids: Vec<i64>,
+    /// Character offsets of the word pieces in the input
+    pub offsets: Vec<Option<Offset>>,
+    /// Word piece masks (e.g. continuation of a word)
+    pub masks: Vec<Mask>,
+    /// Input ids fed to the model for each window, including the special tokens added by the tokenizer
+    pub windows: Vec<Vec<i64>>,
+    /// Maximum sequence length (in tokens) of each window
+    pub max_length: usize,
+    /// True if the input does not fit in a single window of `max_length` tokens. Such inputs are not
+    /// truncated, but split into overlapping windows (see `TokenClassificationConfig::window_overlap`)
+    pub exceeds_max_length: bool,
+}
+
 impl TokenTrait for Token {
     fn offset(&self) -> Option<Offset> {
         self.offset
@@ -187,7 +282,7 @@ impl ConsolidatableTokens<Token> for Vec<Token> {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct InputFeature {
     /// Encoded input ids
     input_ids: Vec<i64>,
@@ -212,6 +307,8 @@ pub enum LabelAggregationOption {
     Last,
     /// The most frequent sub- token is  assigned to the entire token
     Mode,
+    /// The label of the sub token with the highest confidence score is assigned to the entire token
+    Max,
     /// The user can provide a function mapping a `&Vec<Token>` to a `(i64, String)` tuple corresponding to the label index, label String to return
     Custom(LabelAggregationFunction),
 }
@@ -221,7 +318,9 @@ pub enum LabelAggregationOption {
 pub struct TokenClassificationConfig {
     /// Model type
     pub model_type: ModelType,
-    /// Model weights resource (default: pretrained BERT model on CoNLL)
+    /// Model weights resource (default: pretrained BERT model on CoNLL). Weights can also be
+    /// provided from memory using a `BufferResource`. A path to a `.safetensors` file is loaded without
+    /// conversion. With the `onnx` feature enabled, a path to an `.onnx` file runs the model with ONNX Runtime.
     pub model_resource: Box<dyn ResourceProvider + Send>,
     /// Config resource (default: pretrained BERT model on CoNLL)
     pub config_resource: Box<dyn ResourceProvider + Send>,
@@ -231,7 +330,11 @@ pub struct TokenClassificationConfig {
     pub merges_resource: Option<Box<dyn ResourceProvider + Send>>,
     /// Automatically lower case all input upon tokenization (assumes a lower-cased model)
     pub lower_case: bool,
-    /// Flag indicating if the tokenizer should strip accents (normalization). Only used for BERT / ALBERT models
+    /// Flag indicating if the tokenizer should strip accents (normalization). Only used for BERT / ALBERT models.
+    /// For BERT models, accents are stripped if the inputs are lower cased when not set (`None`), following the
+    /// original BERT tokenizer: set it to `Some(false)` for lower-cased models trained without accent stripping, or to
+    /// `Some(true)` for cased models trained with it. Stripping only affects the model inputs: token offsets, texts and
+    /// entity words refer to the original input and keep their accents.
     pub strip_accents: Option<bool>,
     /// Flag indicating if the tokenizer should add a white space before each tokenized input (needed for some Roberta models)
     pub add_prefix_space: Option<bool>,
@@ -239,8 +342,40 @@ pub struct TokenClassificationConfig {
     pub device: Device,
     /// Sub-tokens aggregation method (default: `LabelAggregationOption::First`)
     pub label_aggregation_function: LabelAggregationOption,
-    /// Batch size for predictions
+    /// Batch size for predictions (default: 64). Inputs are processed in batches of this size and padded to the longest sequence of each batch.
+    /// Inputs are sorted by length before batching, so that inputs of similar lengths are processed together.
     pub batch_size: usize,
+    /// Maximum sequence length (in tokens) of each input feature (default: maximum number of position embeddings of the model).
+    /// Longer inputs are processed using overlapping windows, this value may not exceed the model maximum length.
+    pub max_length: Option<usize>,
+    /// Number of tokenized inputs to keep in a least-recently-used cache (default: `None`, caching disabled).
+    /// Useful when the same texts are processed repeatedly, as cached inputs skip tokenization.
+    pub tokenization_cache_size: Option<usize>,
+    /// Number of tokens shared by consecutive windows when an input exceeds `max_length` (default: `max_length / 4`).
+    /// Each token is labelled by the window in which it is the furthest away from the window boundaries.
+    pub window_overlap: Option<usize>,
+    /// Keep the hidden states of all layers, returned by `predict_hidden_states` (default: false).
+    /// Only supported for BERT and RoBERTa models, increases the memory usage of the forward pass.
+    pub output_hidden_states: bool,
+    /// Keep the attention weights of all layers, returned by `predict_hidden_states` (default: false).
+    /// Only supported for BERT and RoBERTa models, increases the memory usage of the forward pass.
+    pub output_attentions: bool,
+    /// Seed for the Libtorch random number generators, set when the model is created (default: `None`).
+    /// Setting a seed also disables cuDNN benchmarking for reproducible kernel selection, see the module
+    /// documentation for the operations that remain non-deterministic on GPU.
+    /// Both settings are global to the process: they also apply to every other model and tensor operation
+    /// of the process, and are not restored when the model is dropped.
+    pub seed: Option<i64>,
+    /// Return label scores as log-probabilities, computed with a log-softmax over the model logits, instead of
+    /// probabilities (default: false). Applies to all scores returned by the model, including the scores of
+    /// consolidated tokens and of the entities returned by the `NERModel`.
+    pub log_probabilities: bool,
+    /// Optional newline-delimited labels file (e.g. `labels.txt`), where the label on line `i` has the label id `i`
+    /// (default: `None`). When provided, it overrides the `id2label` mapping of the model configuration.
+    pub labels_resource: Option<Box<dyn ResourceProvider + Send>>,
+    /// Add the special tokens of the model (e.g. `[CLS]` and `[SEP]` for BERT) to each input window (default: true).
+    /// Disabling this passes the tokenized inputs to the model as-is, for models trained without special tokens.
+    pub add_special_tokens: bool,
 }
 
 impl TokenClassificationConfig {
@@ -282,7 +417,383 @@ impl TokenClassificationConfig {
             device: Device::cuda_if_available(),
             label_aggregation_function,
             batch_size: 64,
+            max_length: None,
+            tokenization_cache_size: None,
+            window_overlap: None,
+            output_hidden_states: false,
+            output_attentions: false,
+            seed: None,
+            log_probabilities: false,
+            labels_resource: None,
+            add_special_tokens: true,
+        }
+    }
+
+    /// Instantiate a new token classification configuration from a local model directory.
+    ///
+    /// The directory is expected to contain the model configuration (`config.json`), the converted weights
+    /// (`rust_model.ot`, as produced by `utils/convert_model.py`, or `model.safetensors` if no converted weights are
+    /// present) and the tokenizer vocabulary (`vocab.txt`,
+    /// or `vocab.json` and `merges.txt` for RoBERTa-based models). The model type is read from the `model_type`
+    /// field of the configuration and defaults to BERT if absent.
+    /// Lower casing of the inputs follows the `do_lower_case` flag of `tokenizer_config.json` (or of `config.json`
+    /// if the tokenizer configuration is not available). If neither file provides it, inputs are not lower cased
+    /// (cased model). The flag can be overridden by setting `lower_case` on the returned configuration.
+    /// Similarly, accent stripping follows the `strip_accents` flag of `tokenizer_config.json` for BERT tokenizers,
+    /// and defaults to the lower casing flag if absent (see `strip_accents`).
+    /// If the directory contains a `labels.txt` file, it is used as the label mapping of the model (see `labels_resource`).
+    ///
+    /// # Arguments
+    ///
+    /// * `model_dir` - Path to the directory containing the model files
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::token_classification::TokenClassificationConfig;
+    ///
+    /// let config = TokenClassificationConfig::from_dir("path/to/bert-ner")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_dir<P: AsRef<Path>>(
+        model_dir: P,
+    ) -> Result<TokenClassificationConfig, RustBertError> {
+        let model_dir = model_dir.as_ref();
+        Self::from_model_files(|file_name| resolve_model_file(model_dir, file_name))
+    }
+
+    /// Create a `TokenClassificationConfig` from a model repository of the Hugging Face model hub. The model files are
+    /// downloaded to the `rustbert` cache directory (`~/.cache/.rustbert/<model_id>` by default, or the directory set by
+    /// the `RUSTBERT_CACHE` environment variable) and reused on subsequent calls if unchanged.
+    ///
+    /// The repository is expected to contain the same files as the directory read by `from_dir`: the model configuration,
+    /// the weights (`rust_model.ot`, or `model.safetensors` if not available) and the tokenizer vocabulary.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_id` - Identifier of the model repository (e.g. `dbmdz/bert-large-cased-finetuned-conll03-english`)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::token_classification::TokenClassificationConfig;
+    ///
+    /// let config =
+    ///     TokenClassificationConfig::from_pretrained("dbmdz/bert-large-cased-finetuned-conll03-english")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "remote")]
+    pub fn from_pretrained(model_id: &str) -> Result<TokenClassificationConfig, RustBertError> {
+        Self::from_pretrained_with_checksums(model_id, &[])
+    }
+
+    /// Create a `TokenClassificationConfig` from a model repository of the Hugging Face model hub, verifying the
+    /// SHA256 checksums of the downloaded files (see `from_pretrained`). Cached files that do not match their checksum
+    /// (for example after an interrupted download) are downloaded again, and an error is returned if the downloaded
+    /// file still does not match.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_id` - Identifier of the model repository (e.g. `dbmdz/bert-large-cased-finetuned-conll03-english`)
+    /// * `checksums` - `&[(&str, &str)]` manifest of (file name, SHA256 checksum) pairs. Files absent from the manifest are not verified.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::token_classification::TokenClassificationConfig;
+    ///
+    /// let config = TokenClassificationConfig::from_pretrained_with_checksums(
+    ///     "dbmdz/bert-large-cased-finetuned-conll03-english",
+    ///     &[(
+    ///         "rust_model.ot",
+    ///         "<sha256 of the weights file, as listed in the model repository>",
+    ///     )],
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "remote")]
+    pub fn from_pretrained_with_checksums(
+        model_id: &str,
+        checksums: &[(&str, &str)],
+    ) -> Result<TokenClassificationConfig, RustBertError> {
+        Self::from_model_files(|file_name| {
+            let resource = RemoteResource::new(
+                &format!("https://huggingface.co/{model_id}/resolve/main/{file_name}"),
+                model_id,
+            );
+            match checksums.iter().find(|(name, _)| *name == file_name) {
+                Some((_, sha256)) => resource.with_sha256(sha256),
+                None => resource,
+            }
+            .get_local_path()
+        })
+    }
+
+    /// Builds the configuration from the model files returned by `resolve_file` (returning an error if a file is not available)
+    fn from_model_files<F>(resolve_file: F) -> Result<TokenClassificationConfig, RustBertError>
+    where
+        F: Fn(&str) -> Result<PathBuf, RustBertError>,
+    {
+        let config_path = resolve_file("config.json")?;
+        let model_type_config = ModelTypeConfig::from_file(&config_path);
+        let model_type = match model_type_config.model_type {
+            Some(model_type) => {
+                let value = serde_json::Value::String(model_type.clone());
+                serde_json::from_value::<ModelType>(value).map_err(|_| {
+                    RustBertError::InvalidConfigurationError(format!(
+                        "Unknown model type {model_type} in configuration file"
+                    ))
+                })?
+            }
+            None => ModelType::Bert,
+        };
+        let (vocab_path, merges_path) = match model_type {
+            ModelType::Bert
+            | ModelType::DistilBert
+            | ModelType::Electra
+            | ModelType::MobileBert => (resolve_file("vocab.txt")?, None),
+            ModelType::Roberta | ModelType::Longformer => (
+                resolve_file("vocab.json")?,
+                Some(resolve_file("merges.txt")?),
+            ),
+            _ => {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "Loading a token classification model from a directory is not supported for {model_type:?}",
+                )));
+            }
+        };
+        let weights_path = match resolve_file("rust_model.ot") {
+            Ok(weights_path) => weights_path,
+            Err(error) => resolve_file("model.safetensors").map_err(|_| error)?,
+        };
+        let tokenizer_config = resolve_file("tokenizer_config.json")
+            .ok()
+            .map(|tokenizer_config_path| TokenizerConfigFile::from_file(&tokenizer_config_path));
+        let lower_case = tokenizer_config
+            .as_ref()
+            .and_then(|tokenizer_config| tokenizer_config.do_lower_case)
+            .or(model_type_config.do_lower_case)
+            .unwrap_or(false);
+        // Accent stripping is only configurable for WordPiece (BERT) tokenizers
+        let strip_accents = if matches!(
+            model_type,
+            ModelType::Bert | ModelType::DistilBert | ModelType::Electra | ModelType::MobileBert
+        ) {
+            tokenizer_config.and_then(|tokenizer_config| tokenizer_config.strip_accents)
+        } else {
+            None
+        };
+
+        let mut config = TokenClassificationConfig::new(
+            model_type,
+            LocalResource::from(weights_path),
+            LocalResource::from(config_path),
+            LocalResource::from(vocab_path),
+            merges_path.map(LocalResource::from),
+            lower_case,
+            strip_accents,
+            None,
+            LabelAggregationOption::First,
+        );
+        if let Ok(labels_path) = resolve_file("labels.txt") {
+            config.labels_resource = Some(Box::new(LocalResource::from(labels_path)));
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelTypeConfig {
+    #[serde(default)]
+    model_type: Option<String>,
+    #[serde(default)]
+    do_lower_case: Option<bool>,
+    #[serde(default)]
+    cls_token_id: Option<i64>,
+    #[serde(default)]
+    sep_token_id: Option<i64>,
+    #[serde(default)]
+    pad_token_id: Option<i64>,
+}
+
+impl Config for ModelTypeConfig {}
+
+/// Returns an error if the model configuration file declares a model type that does not match `model_type`
+/// (for example an ALBERT checkpoint loaded as a BERT model). Unknown or absent declared types are accepted.
+fn check_model_type(model_type: ModelType, config_path: &Path) -> Result<(), RustBertError> {
+    let declared_model_type =
+        ModelTypeConfig::from_file(config_path)
+            .model_type
+            .and_then(|declared| {
+                serde_json::from_value::<ModelType>(serde_json::Value::String(declared)).ok()
+            });
+    match declared_model_type {
+        Some(declared_model_type)
+            if (declared_model_type != model_type)
+                && !matches!(
+                    (declared_model_type, model_type),
+                    (ModelType::Roberta, ModelType::XLMRoberta)
+                        | (ModelType::XLMRoberta, ModelType::Roberta)
+                ) =>
+        {
+            Err(RustBertError::InvalidConfigurationError(format!(
+                "Unsupported model type: the configuration file declares a {declared_model_type:?} model, but {model_type:?} was requested"
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// # Special token ids used to build the inputs of a `TokenClassificationModel`
+/// The ids are read from the `cls_token_id`, `sep_token_id` and `pad_token_id` fields of the model configuration
+/// file if present, and default to the ids of the special tokens of the tokenizer vocabulary otherwise.
+pub struct SpecialTokenIds {
+    /// Id of the classification token inserted at the start of each input (e.g. `[CLS]` for BERT), if any
+    pub cls: Option<i64>,
+    /// Id of the separator token inserted at the end of each input (e.g. `[SEP]` for BERT), if any
+    pub sep: Option<i64>,
+    /// Id of the padding token used to pad the inputs of a batch to the same length
+    pub pad: i64,
+}
+
+/// Resolves the special token ids of the model, returning them along with the (tokenizer id, model id) pairs
+/// of the special tokens inserted by the tokenizer that need to be replaced by the model ids.
+fn resolve_special_token_ids(
+    tokenizer: &TokenizerOption,
+    config_path: &Path,
+) -> Result<(SpecialTokenIds, Vec<(i64, i64)>), RustBertError> {
+    let model_config = ModelTypeConfig::from_file(config_path);
+    let tokenizer_sep = tokenizer.get_sep_id();
+    let tokenizer_cls = tokenizer
+        .build_input_with_special_tokens(
+            TokenIdsWithOffsets {
+                ids: vec![],
+                offsets: vec![],
+                reference_offsets: vec![],
+                masks: vec![],
+            },
+            None,
+        )
+        .token_ids
+        .into_iter()
+        .find(|token_id| Some(*token_id) != tokenizer_sep);
+    let pad = model_config
+        .pad_token_id
+        .or_else(|| tokenizer.get_pad_id())
+        .ok_or_else(|| {
+            RustBertError::InvalidConfigurationError(
+                "No padding token id: the tokenizer has no padding token and the model configuration does not define `pad_token_id`".to_string(),
+            )
+        })?;
+    let special_token_ids = SpecialTokenIds {
+        cls: model_config.cls_token_id.or(tokenizer_cls),
+        sep: model_config.sep_token_id.or(tokenizer_sep),
+        pad,
+    };
+    let special_token_remap = [
+        (tokenizer_cls, special_token_ids.cls),
+        (tokenizer_sep, special_token_ids.sep),
+    ]
+    .iter()
+    .filter_map(|ids| match ids {
+        (Some(tokenizer_id), Some(model_id)) if tokenizer_id != model_id => {
+            Some((*tokenizer_id, *model_id))
+        }
+        _ => None,
+    })
+    .collect();
+    Ok((special_token_ids, special_token_remap))
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenizerConfigFile {
+    #[serde(default)]
+    do_lower_case: Option<bool>,
+    #[serde(default)]
+    strip_accents: Option<bool>,
+}
+
+impl Config for TokenizerConfigFile {}
+
+/// Name of the weight of the classification layer, shared by the token classification heads of all architectures
+const CLASSIFIER_WEIGHT: &str = "classifier.weight";
+
+/// Checks that the output dimension of the classification head matches the number of labels of the label mapping
+fn check_num_labels(num_classifier_labels: i64, num_labels: usize) -> Result<(), RustBertError> {
+    if num_classifier_labels as usize != num_labels {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "The classification head of the model weights predicts {num_classifier_labels} labels, but {num_labels} labels are defined in the configuration"
+        )));
+    }
+    Ok(())
+}
+
+/// Loads the model weights into the `VarStore`, checking that the output dimension of the classification head
+/// of the weights matches the number of labels of the label mapping
+fn load_classifier_weights(
+    resource: &(impl ResourceProvider + ?Sized),
+    var_store: &mut VarStore,
+    num_labels: usize,
+) -> Result<(), RustBertError> {
+    load_weights(resource, var_store).map_err(|err| {
+        match weight_shape(resource, CLASSIFIER_WEIGHT) {
+            Some(shape) => check_num_labels(shape[0], num_labels).err().unwrap_or(err),
+            None => err,
         }
+    })?;
+    match var_store.variables().get(CLASSIFIER_WEIGHT) {
+        Some(classifier_weight) => check_num_labels(classifier_weight.size()[0], num_labels),
+        None => Ok(()),
+    }
+}
+
+/// Reads a newline-delimited labels file, the label on line `i` being mapped to the label id `i`
+fn read_labels_file(path: &Path) -> Result<HashMap<i64, String>, RustBertError> {
+    let content = std::fs::read_to_string(path)?;
+    let labels = content.lines().map(str::trim).collect::<Vec<&str>>();
+    let num_labels = labels
+        .iter()
+        .rposition(|label| !label.is_empty())
+        .map_or(0, |position| position + 1);
+    if num_labels == 0 {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "The labels file {} is empty",
+            path.display()
+        )));
+    }
+    if let Some(position) = labels[..num_labels]
+        .iter()
+        .position(|label| label.is_empty())
+    {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "Empty label on line {} of the labels file {}",
+            position + 1,
+            path.display()
+        )));
+    }
+    Ok(labels[..num_labels]
+        .iter()
+        .enumerate()
+        .map(|(id, label)| (id as i64, label.to_string()))
+        .collect())
+}
+
+fn resolve_model_file(model_dir: &Path, file_name: &str) -> Result<PathBuf, RustBertError> {
+    let path = model_dir.join(file_name);
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(RustBertError::IOError(format!(
+            "Could not find {file_name} in model directory {}",
+            model_dir.display()
+        )))
     }
 }
 
@@ -331,6 +842,83 @@ pub enum TokenClassificationOption {
     Longformer(LongformerForTokenClassification),
     /// FNet for Token Classification
     FNet(FNetForTokenClassification),
+    /// Model exported to ONNX, run with ONNX Runtime (requires the `onnx` feature)
+    #[cfg(feature = "onnx")]
+    ONNX(ONNXTokenClassifier),
+}
+
+/// # Forward pass of a token classification model
+/// Abstracts the backend running the model (Libtorch through `tch`, or ONNX Runtime with the `onnx` feature) from
+/// the pre- and post-processing of the token classification pipeline. `TokenClassificationOption` implements it
+/// for both backends.
+///
+/// Inputs and outputs are still `tch` tensors: this is a first step towards alternative backends, and does not
+/// make the pipelines compile to targets libtorch does not support (e.g. `wasm32-unknown-unknown`).
+///
+/// # Example
+///
+/// ```no_run
+/// use rust_bert::pipelines::token_classification::TokenClassificationBackend;
+/// use rust_bert::RustBertError;
+/// use tch::Tensor;
+///
+/// fn predicted_labels<B: TokenClassificationBackend>(
+///     backend: &B,
+///     input_ids: &Tensor,
+///     attention_mask: &Tensor,
+/// ) -> Result<Tensor, RustBertError> {
+///     Ok(backend.forward(input_ids, attention_mask, None)?.argmax(-1, false))
+/// }
+/// ```
+pub trait TokenClassificationBackend {
+    /// Computes the token classification logits, in inference mode (dropout disabled). Gradients of Libtorch
+    /// models are tracked unless the forward pass runs within `tch::no_grad`.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Input tensor of shape (*batch size*, *sequence_length*)
+    /// * `attention_mask` - Mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1.
+    /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). If None set to 0.
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*batch size*, *sequence_length*, *num_labels*) containing the logits for each token
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        attention_mask: &Tensor,
+        token_type_ids: Option<&Tensor>,
+    ) -> Result<Tensor, RustBertError>;
+}
+
+impl TokenClassificationBackend for TokenClassificationOption {
+    fn forward(
+        &self,
+        input_ids: &Tensor,
+        attention_mask: &Tensor,
+        token_type_ids: Option<&Tensor>,
+    ) -> Result<Tensor, RustBertError> {
+        self.forward_t(
+            Some(input_ids),
+            Some(attention_mask),
+            token_type_ids,
+            None,
+            None,
+            false,
+        )
+    }
+}
+
+/// # Hidden states and attention weights of all layers of a `TokenClassificationModel`
+/// The first dimension of each tensor indexes the windows the input was split into (a single window unless
+/// the input exceeds the maximum length of the model).
+pub struct TokenClassificationHiddenStates {
+    /// Hidden states of each layer, of shape (windows, sequence length, hidden size).
+    /// Empty unless `output_hidden_states` is set in the `TokenClassificationConfig`.
+    pub all_hidden_states: Vec<Tensor>,
+    /// Attention weights of each layer, of shape (windows, attention heads, sequence length, sequence length).
+    /// Empty unless `output_attentions` is set in the `TokenClassificationConfig`.
+    pub all_attentions: Vec<Tensor>,
 }
 
 impl TokenClassificationOption {
@@ -504,6 +1092,8 @@ impl TokenClassificationOption {
             Self::XLNet(_) => ModelType::XLNet,
             Self::Longformer(_) => ModelType::Longformer,
             Self::FNet(_) => ModelType::FNet,
+            #[cfg(feature = "onnx")]
+            Self::ONNX(ref model) => model.model_type(),
         }
     }
 
@@ -515,8 +1105,8 @@ impl TokenClassificationOption {
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
         train: bool,
-    ) -> Tensor {
-        match *self {
+    ) -> Result<Tensor, RustBertError> {
+        Ok(match *self {
             Self::Bert(ref model) => {
                 model
                     .forward_t(
@@ -538,8 +1128,7 @@ impl TokenClassificationOption {
                         position_ids,
                         input_embeds,
                         train,
-                    )
-                    .expect("Error in DeBERTa forward_t")
+                    )?
                     .logits
             }
             Self::DebertaV2(ref model) => {
@@ -551,20 +1140,17 @@ impl TokenClassificationOption {
                         position_ids,
                         input_embeds,
                         train,
-                    )
-                    .expect("Error in DeBERTa V2 forward_t")
+                    )?
                     .logits
             }
             Self::DistilBert(ref model) => {
                 model
-                    .forward_t(input_ids, mask, input_embeds, train)
-                    .expect("Error in distilbert forward_t")
+                    .forward_t(input_ids, mask, input_embeds, train)?
                     .logits
             }
             Self::MobileBert(ref model) => {
                 model
-                    .forward_t(input_ids, None, None, input_embeds, mask, train)
-                    .expect("Error in mobilebert forward_t")
+                    .forward_t(input_ids, None, None, input_embeds, mask, train)?
                     .logits
             }
             Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
@@ -627,16 +1213,47 @@ impl TokenClassificationOption {
                         position_ids,
                         input_embeds,
                         train,
-                    )
-                    .expect("Error in longformer forward_t")
+                    )?
                     .logits
             }
             Self::FNet(ref model) => {
                 model
-                    .forward_t(input_ids, token_type_ids, position_ids, input_embeds, train)
-                    .expect("Error in fnet forward_t")
+                    .forward_t(input_ids, token_type_ids, position_ids, input_embeds, train)?
                     .logits
             }
+            #[cfg(feature = "onnx")]
+            Self::ONNX(ref model) => model.forward(
+                input_ids.ok_or_else(|| {
+                    RustBertError::ValueError(
+                        "Input ids must be provided for ONNX models".to_string(),
+                    )
+                })?,
+                mask.ok_or_else(|| {
+                    RustBertError::ValueError(
+                        "Attention mask must be provided for ONNX models".to_string(),
+                    )
+                })?,
+                token_type_ids,
+            )?,
+        })
+    }
+
+    /// Returns the hidden states and attention weights of all layers, if kept by the model (BERT and RoBERTa only)
+    fn forward_hidden_states(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+    ) -> (Option<Vec<Tensor>>, Option<Vec<Tensor>>) {
+        match *self {
+            Self::Bert(ref model) => {
+                let output = model.forward_t(input_ids, mask, None, None, None, false);
+                (output.all_hidden_states, output.all_attentions)
+            }
+            Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
+                let output = model.forward_t(input_ids, mask, None, None, None, false);
+                (output.all_hidden_states, output.all_attentions)
+            }
+            _ => (None, None),
         }
     }
 }
@@ -646,10 +1263,17 @@ pub struct TokenClassificationModel {
     tokenizer: TokenizerOption,
     token_sequence_classifier: TokenClassificationOption,
     label_mapping: HashMap<i64, String>,
+    model_config: ConfigOption,
     var_store: VarStore,
     label_aggregation_function: LabelAggregationOption,
     max_length: usize,
+    window_overlap: usize,
     batch_size: usize,
+    feature_cache: Option<Mutex<FeatureCache>>,
+    log_probabilities: bool,
+    add_special_tokens: bool,
+    special_token_ids: SpecialTokenIds,
+    special_token_remap: Vec<(i64, i64)>,
 }
 
 impl TokenClassificationModel {
@@ -673,8 +1297,8 @@ impl TokenClassificationModel {
         config: TokenClassificationConfig,
     ) -> Result<TokenClassificationModel, RustBertError> {
         let config_path = config.config_resource.get_local_path()?;
+        check_model_type(config.model_type, &config_path)?;
         let vocab_path = config.vocab_resource.get_local_path()?;
-        let weights_path = config.model_resource.get_local_path()?;
         let merges_path = if let Some(merges_resource) = &config.merges_resource {
             Some(merges_resource.get_local_path()?)
         } else {
@@ -682,6 +1306,10 @@ impl TokenClassificationModel {
         };
         let device = config.device;
         let label_aggregation_function = config.label_aggregation_function;
+        if let Some(seed) = config.seed {
+            tch::manual_seed(seed);
+            tch::Cuda::cudnn_set_benchmark(false);
+        }
 
         let tokenizer = TokenizerOption::from_file(
             config.model_type,
@@ -691,28 +1319,171 @@ impl TokenClassificationModel {
             config.strip_accents,
             config.add_prefix_space,
         )?;
+        let (special_token_ids, special_token_remap) =
+            resolve_special_token_ids(&tokenizer, &config_path)?;
+        debug!(
+            "Loading {:?} token classification model on {:?}",
+            config.model_type, device
+        );
         let mut var_store = VarStore::new(device);
-        let model_config = ConfigOption::from_file(config.model_type, config_path);
-        let max_length = model_config
-            .get_max_len()
-            .map(|v| v as usize)
-            .unwrap_or(usize::MAX);
-        let token_sequence_classifier =
-            TokenClassificationOption::new(config.model_type, var_store.root(), &model_config)?;
-        let label_mapping = model_config.get_label_mapping().clone();
+        let mut model_config = ConfigOption::from_file(config.model_type, config_path);
+        if config.output_hidden_states || config.output_attentions {
+            match &mut model_config {
+                ConfigOption::Bert(model_config) | ConfigOption::Roberta(model_config) => {
+                    model_config.output_hidden_states = Some(config.output_hidden_states);
+                    model_config.output_attentions = Some(config.output_attentions);
+                }
+                _ => {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "Returning hidden states and attentions is not supported for {:?}",
+                        config.model_type
+                    )));
+                }
+            }
+        }
+        let max_length = match (config.max_length, model_config.get_max_len()) {
+            (Some(max_length), Some(model_max_length))
+                if max_length > model_max_length as usize =>
+            {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "max_length ({max_length}) exceeds the maximum sequence length supported by the model ({model_max_length})"
+                )));
+            }
+            (Some(max_length), _) => max_length,
+            (None, Some(model_max_length)) => model_max_length as usize,
+            (None, None) => usize::MAX,
+        };
+        let window_overlap = config.window_overlap.unwrap_or(max_length / 4);
+        let num_special_tokens = if config.add_special_tokens {
+            Self::num_special_tokens(&tokenizer)
+        } else {
+            0
+        };
+        let max_content_length = max_length.saturating_sub(num_special_tokens);
+        if window_overlap >= max_content_length {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "window_overlap ({window_overlap}) must be smaller than the number of input tokens per window ({max_content_length})"
+            )));
+        }
+        if let Some(labels_resource) = &config.labels_resource {
+            model_config
+                .set_label_mapping(read_labels_file(&labels_resource.get_local_path()?)?)?;
+        }
+        let label_mapping = model_config
+            .get_label_mapping()
+            .map_err(|_| {
+                RustBertError::InvalidConfigurationError(
+                    "No label mapping available: the model configuration does not define `id2label` (or `label2id`) and no labels file was provided".to_string(),
+                )
+            })?
+            .clone();
         let batch_size = config.batch_size;
-        var_store.load(weights_path)?;
+        let feature_cache = config
+            .tokenization_cache_size
+            .filter(|cache_size| *cache_size > 0)
+            .map(|cache_size| Mutex::new(FeatureCache::new(cache_size)));
+        let token_sequence_classifier =
+            match Self::load_onnx_classifier(config.model_type, config.model_resource.as_ref())? {
+                Some(onnx_classifier) => onnx_classifier,
+                None => {
+                    let token_sequence_classifier = TokenClassificationOption::new(
+                        config.model_type,
+                        var_store.root(),
+                        &model_config,
+                    )?;
+                    load_classifier_weights(
+                        config.model_resource.as_ref(),
+                        &mut var_store,
+                        label_mapping.len(),
+                    )?;
+                    token_sequence_classifier
+                }
+            };
         Ok(TokenClassificationModel {
             tokenizer,
             token_sequence_classifier,
             label_mapping,
+            model_config,
             var_store,
             label_aggregation_function,
             max_length,
+            window_overlap,
             batch_size,
+            feature_cache,
+            log_probabilities: config.log_probabilities,
+            add_special_tokens: config.add_special_tokens,
+            special_token_ids,
+            special_token_remap,
         })
     }
 
+    /// Loads the model with ONNX Runtime if the model resource points to an `.onnx` file
+    #[cfg(feature = "onnx")]
+    fn load_onnx_classifier(
+        model_type: ModelType,
+        model_resource: &(dyn ResourceProvider + Send),
+    ) -> Result<Option<TokenClassificationOption>, RustBertError> {
+        match model_resource.get_resource()? {
+            Resource::PathBuf(path)
+                if path
+                    .extension()
+                    .map_or(false, |extension| extension == "onnx") =>
+            {
+                Ok(Some(TokenClassificationOption::ONNX(
+                    ONNXTokenClassifier::new(path, model_type)?,
+                )))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    #[cfg(not(feature = "onnx"))]
+    fn load_onnx_classifier(
+        _model_type: ModelType,
+        _model_resource: &(dyn ResourceProvider + Send),
+    ) -> Result<Option<TokenClassificationOption>, RustBertError> {
+        Ok(None)
+    }
+
+    fn num_special_tokens(tokenizer: &TokenizerOption) -> usize {
+        tokenizer
+            .build_input_with_special_tokens(
+                TokenIdsWithOffsets {
+                    ids: vec![],
+                    offsets: vec![],
+                    reference_offsets: vec![],
+                    masks: vec![],
+                },
+                None,
+            )
+            .token_ids
+            .len()
+    }
+
+    fn get_features(&self, input: &str, example_index: usize) -> Vec<InputFeature> {
+        let feature_cache = match &self.feature_cache {
+            Some(feature_cache) => feature_cache,
+            None => return self.generate_features(input, example_index),
+        };
+        let key = (input.to_string(), self.max_length);
+        let cached_features = feature_cache.lock().unwrap().get(&key);
+        match cached_features {
+            Some(cached_features) => {
+                let mut features = cached_features.as_ref().clone();
+                for feature in features.iter_mut() {
+                    feature.example_index = example_index;
+                }
+                features
+            }
+            None => {
+                let features = self.generate_features(input, example_index);
+                let cached_features = Arc::new(features.clone());
+                feature_cache.lock().unwrap().insert(key, cached_features);
+                features
+            }
+        }
+    }
+
     fn generate_features<S>(&self, input: S, example_index: usize) -> Vec<InputFeature>
     where
         S: AsRef<str>,
@@ -726,23 +1497,22 @@ impl TokenClassificationModel {
             reference_offsets: tokenized_input.reference_offsets,
             masks: tokenized_input.masks,
         };
+        self.generate_features_from_encoded(encoded_input, example_index)
+    }
 
-        let sequence_added_tokens = self
-            .tokenizer
-            .build_input_with_special_tokens(
-                TokenIdsWithOffsets {
-                    ids: vec![],
-                    offsets: vec![],
-                    reference_offsets: vec![],
-                    masks: vec![],
-                },
-                None,
-            )
-            .token_ids
-            .len();
+    fn generate_features_from_encoded(
+        &self,
+        encoded_input: TokenIdsWithOffsets,
+        example_index: usize,
+    ) -> Vec<InputFeature> {
+        let sequence_added_tokens = if self.add_special_tokens {
+            Self::num_special_tokens(&self.tokenizer)
+        } else {
+            0
+        };
 
         let max_content_length = self.max_length - sequence_added_tokens;
-        let doc_stride = self.max_length / 4;
+        let doc_stride = self.window_overlap;
 
         let mut spans: Vec<InputFeature> = vec![];
         let mut start_token = 0_usize;
@@ -757,22 +1527,36 @@ impl TokenClassificationModel {
                 masks: encoded_input.masks[start_token..end_token].to_vec(),
             };
 
-            let encoded_span = self
-                .tokenizer
-                .build_input_with_special_tokens(sub_encoded_input, None);
+            let (input_ids, offsets, mask) = if self.add_special_tokens {
+                let mut encoded_span = self
+                    .tokenizer
+                    .build_input_with_special_tokens(sub_encoded_input, None);
+                self.remap_special_tokens(&mut encoded_span.token_ids, &encoded_span.mask);
+                (
+                    encoded_span.token_ids,
+                    encoded_span.token_offsets,
+                    encoded_span.mask,
+                )
+            } else {
+                (
+                    sub_encoded_input.ids,
+                    sub_encoded_input.offsets,
+                    sub_encoded_input.masks,
+                )
+            };
 
             let reference_feature = self.get_reference_feature_flag(
                 start_token,
                 end_token,
                 total_length,
                 doc_stride,
-                &encoded_span,
+                &mask,
             );
 
             let feature = InputFeature {
-                input_ids: encoded_span.token_ids,
-                offsets: encoded_span.token_offsets,
-                mask: encoded_span.mask,
+                input_ids,
+                offsets,
+                mask,
                 reference_feature,
                 example_index,
             };
@@ -782,22 +1566,51 @@ impl TokenClassificationModel {
             }
             start_token = end_token - doc_stride;
         }
+        if spans.len() > 1 {
+            debug!(
+                "Input {} of {} tokens exceeds the {} tokens of a window and was split into {} overlapping windows",
+                example_index,
+                total_length,
+                max_content_length,
+                spans.len()
+            );
+        }
         spans
     }
 
+    /// Replaces the special tokens inserted by the tokenizer by the special token ids of the model configuration
+    fn remap_special_tokens(&self, input_ids: &mut [i64], mask: &[Mask]) {
+        if self.special_token_remap.is_empty() {
+            return;
+        }
+        for (input_id, _) in input_ids
+            .iter_mut()
+            .zip(mask)
+            .filter(|(_, mask)| **mask == Mask::Special)
+        {
+            if let Some((_, model_id)) = self
+                .special_token_remap
+                .iter()
+                .find(|(tokenizer_id, _)| tokenizer_id == input_id)
+            {
+                *input_id = *model_id;
+            }
+        }
+    }
+
     fn get_reference_feature_flag(
         &self,
         start_token: usize,
         end_token: usize,
         total_length: usize,
         doc_stride: usize,
-        encoded_span: &TokenizedInput,
+        mask: &[Mask],
     ) -> Vec<bool> {
         // set halfway through the doc_stride to be false if the feature is not the first/last
         let start_cutoff = if start_token > 0 {
             let leading_special_tokens = {
                 let mut counter = 0;
-                let mut masks = encoded_span.mask.iter();
+                let mut masks = mask.iter();
                 while masks.next().unwrap_or(&Mask::None) == &Mask::Special {
                     counter += 1;
                 }
@@ -810,17 +1623,17 @@ impl TokenClassificationModel {
         let end_cutoff = if end_token < total_length {
             let trailing_special_tokens = {
                 let mut counter = 0;
-                let mut masks = encoded_span.mask.iter().rev();
+                let mut masks = mask.iter().rev();
                 while masks.next().unwrap_or(&Mask::None) == &Mask::Special {
                     counter += 1;
                 }
                 counter
             };
-            encoded_span.token_ids.len() - doc_stride / 2 - trailing_special_tokens
+            mask.len() - doc_stride / 2 - trailing_special_tokens
         } else {
-            encoded_span.token_ids.len()
+            mask.len()
         };
-        let mut reference_feature = vec![true; encoded_span.token_ids.len()];
+        let mut reference_feature = vec![true; mask.len()];
         reference_feature[..start_cutoff]
             .iter_mut()
             .for_each(|v| *v = false);
@@ -830,79 +1643,816 @@ impl TokenClassificationModel {
         reference_feature
     }
 
-    /// Classify tokens in a text sequence
+    /// Get a reference to the model configuration (e.g. hidden size, number of layers)
+    pub fn get_config(&self) -> &ConfigOption {
+        &self.model_config
+    }
+
+    /// Returns the special token ids used to build the model inputs (see `SpecialTokenIds`)
+    pub fn get_special_token_ids(&self) -> SpecialTokenIds {
+        self.special_token_ids
+    }
+
+    /// Get a reference to the mapping from label indices to label names
+    pub fn get_label_mapping(&self) -> &HashMap<i64, String> {
+        &self.label_mapping
+    }
+
+    /// Returns true if label scores are returned as log-probabilities (see `TokenClassificationConfig::log_probabilities`)
+    pub fn log_probabilities(&self) -> bool {
+        self.log_probabilities
+    }
+
+    /// Tokenizes an input without running the model, exposing the word pieces and the windows that a prediction
+    /// would feed to the model. Useful to inspect how a text is split into sub-tokens or whether it exceeds the
+    /// configured maximum length.
     ///
     /// # Arguments
     ///
-    /// * `input` - `&[&str]` Array of texts to extract entities from.
-    /// * `consolidate_subtokens` - bool flag indicating if subtokens should be consolidated at the token level
-    /// * `return_special` - bool flag indicating if labels for special tokens should be returned
+    /// * `input` - Text to tokenize
     ///
     /// # Returns
     ///
-    /// * `Vec<Vec<Token>>` containing Tokens with associated labels (for example POS tags) for each input provided
-    ///
-    /// # Example
-    ///
-    /// ```no_run
-    /// # fn main() -> anyhow::Result<()> {
-    /// # use rust_bert::pipelines::token_classification::TokenClassificationModel;
-    ///
-    /// let ner_model = TokenClassificationModel::new(Default::default())?;
-    /// let input = [
-    ///     "My name is Amy. I live in Paris.",
-    ///     "Paris is a city in France.",
-    /// ];
-    /// let output = ner_model.predict(&input, true, true);
-    /// # Ok(())
-    /// # }
+    /// * `TokenizationDebug` containing the word pieces, their ids and offsets and the model input windows
+    pub fn tokenize_debug(&self, input: &str) -> TokenizationDebug {
+        let tokenized_input = self.tokenizer.tokenize_with_offsets(input);
+        let token_ids = self
+            .tokenizer
+            .convert_tokens_to_ids(&tokenized_input.tokens);
+        let encoded_input = TokenIdsWithOffsets {
+            ids: token_ids.clone(),
+            offsets: tokenized_input.offsets.clone(),
+            reference_offsets: tokenized_input.reference_offsets,
+            masks: tokenized_input.masks.clone(),
+        };
+        let windows = self
+            .generate_features_from_encoded(encoded_input, 0)
+            .into_iter()
+            .map(|feature| feature.input_ids)
+            .collect::<Vec<Vec<i64>>>();
+        TokenizationDebug {
+            tokens: tokenized_input.tokens,
+            token_ids,
+            offsets: tokenized_input.offsets,
+            masks: tokenized_input.masks,
+            exceeds_max_length: windows.len() > 1,
+            windows,
+            max_length: self.max_length,
+        }
+    }
+
+    /// Cast the model weights to half precision (`Kind::Half`), reducing memory usage and speeding up
+    /// inference on GPU. Label probabilities are still computed in single precision for numerical stability.
+    pub fn half(&mut self) {
+        self.var_store.half();
+    }
+
+    /// Cast the model weights to single precision (`Kind::Float`)
+    pub fn float(&mut self) {
+        self.var_store.float();
+    }
+
+    /// Move the model weights to a different device in place (e.g. to free GPU memory by moving
+    /// the model to `Device::Cpu`). Subsequent predictions run on the new device.
+    pub fn to_device(&mut self, device: Device) {
+        self.var_store.set_device(device);
+    }
+
+    /// Get the device the model weights are placed on
+    pub fn device(&self) -> Device {
+        self.var_store.device()
+    }
+
+    /// Get a reference to the variable store holding the model weights, for example to build an optimizer
+    /// for fine-tuning (see `train_step`)
+    pub fn var_store(&self) -> &VarStore {
+        &self.var_store
+    }
+
+    /// Returns the number of parameters of the model, summing the number of elements of all tensors of the
+    /// variable store. Models run with ONNX Runtime hold no weights in the variable store and return 0.
+    pub fn num_parameters(&self) -> i64 {
+        self.var_store
+            .variables()
+            .values()
+            .map(|tensor| tensor.numel() as i64)
+            .sum()
+    }
+
+    /// Get a mutable reference to the variable store holding the model weights, for example to save
+    /// fine-tuned weights or freeze some of the model layers
+    pub fn var_store_mut(&mut self) -> &mut VarStore {
+        &mut self.var_store
+    }
+
+    /// Computes the token classification loss in training mode (dropout enabled and gradients tracked).
+    /// Not supported for models run with ONNX Runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Input tensor of shape (*batch size*, *sequence_length*)
+    /// * `attention_mask` - Mask tensor of shape (*batch size*, *sequence_length*), with 1 for tokens to attend to and 0 for padding
+    /// * `labels` - Label index tensor of shape (*batch size*, *sequence_length*). Positions labelled -100
+    /// (e.g. padding, special tokens or continuation sub-tokens) are ignored.
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` scalar mean cross-entropy loss over the labelled positions
+    pub fn forward_loss(
+        &self,
+        input_ids: &Tensor,
+        attention_mask: &Tensor,
+        labels: &Tensor,
+    ) -> Tensor {
+        let device = self.var_store.device();
+        let input_ids = input_ids.to(device);
+        let token_type_ids = input_ids.zeros_like();
+        let logits = self
+            .token_sequence_classifier
+            .forward_t(
+                Some(&input_ids),
+                Some(&attention_mask.to(device)),
+                Some(&token_type_ids),
+                None,
+                None,
+                true,
+            )
+            .expect("Error in token classification forward_t");
+        let num_labels = *logits.size().last().unwrap();
+        logits.view((-1, num_labels)).cross_entropy_loss::<Tensor>(
+            &labels.to_kind(Kind::Int64).to(device).view(-1),
+            None,
+            Reduction::Mean,
+            -100,
+            0.0,
+        )
+    }
+
+    /// Runs a single fine-tuning step: computes the loss on the batch provided (see `forward_loss`),
+    /// back-propagates it and updates the model weights with the optimizer.
+    ///
+    /// # Arguments
+    ///
+    /// * `optimizer` - `tch::nn::Optimizer` built from the model variable store (see `var_store`)
+    /// * `input_ids` - Input tensor of shape (*batch size*, *sequence_length*)
+    /// * `attention_mask` - Mask tensor of shape (*batch size*, *sequence_length*)
+    /// * `labels` - Label index tensor of shape (*batch size*, *sequence_length*), -100 for ignored positions
+    ///
+    /// # Returns
+    ///
+    /// * `f64` loss value for the batch, before the weights update
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    /// use tch::nn::OptimizerConfig;
+    /// use tch::{nn, Tensor};
+    ///
+    /// let mut model = TokenClassificationModel::new(Default::default())?;
+    /// let mut optimizer = nn::Adam::default().build(model.var_store(), 5e-5)?;
+    ///
+    /// let input_ids = Tensor::of_slice(&[101i64, 1422, 1271, 1110, 10280, 102]).unsqueeze(0);
+    /// let attention_mask = input_ids.ones_like();
+    /// let labels = Tensor::of_slice(&[-100i64, 0, 0, 0, 4, -100]).unsqueeze(0);
+    /// for _ in 0..3 {
+    ///     let loss = model.train_step(&mut optimizer, &input_ids, &attention_mask, &labels);
+    ///     println!("loss: {loss}");
+    /// }
+    /// model.var_store().save("fine_tuned.ot")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn train_step(
+        &mut self,
+        optimizer: &mut nn::Optimizer,
+        input_ids: &Tensor,
+        attention_mask: &Tensor,
+        labels: &Tensor,
+    ) -> f64 {
+        let loss = self.forward_loss(input_ids, attention_mask, labels);
+        optimizer.backward_step(&loss);
+        loss.double_value(&[])
+    }
+
+    /// Classify tokens in a text sequence
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    /// * `consolidate_subtokens` - bool flag indicating if subtokens should be consolidated at the token level
+    /// * `return_special` - bool flag indicating if labels for special tokens should be returned
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Token>>` containing Tokens with associated labels (for example POS tags) for each input provided.
+    /// Tokens whose predicted label index is missing from the model label mapping are skipped (a warning is logged).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the forward pass of the model fails, for example when ONNX Runtime rejects the inputs of a model
+    /// run with the `onnx` feature. The other prediction methods share this behaviour.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    ///
+    /// let ner_model = TokenClassificationModel::new(Default::default())?;
+    /// let input = [
+    ///     "My name is Amy. I live in Paris.",
+    ///     "Paris is a city in France.",
+    /// ];
+    /// let output = ner_model.predict(&input, true, true);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict<S>(
+        &self,
+        input: &[S],
+        consolidate_sub_tokens: bool,
+        return_special: bool,
+    ) -> Vec<Vec<Token>>
+    where
+        S: AsRef<str>,
+    {
+        let mut tokens =
+            self.predict_tokens(input, return_special, None, None, |token, _, _, _, _| token);
+
+        if consolidate_sub_tokens {
+            self.consolidate_tokens(&mut tokens, &self.label_aggregation_function);
+        }
+        tokens
+    }
+
+    /// Classify tokens in a text sequence, reporting progress after each batch processed by the model.
+    /// The inputs are processed in batches of `batch_size` windows (as set in the `TokenClassificationConfig`).
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    /// * `consolidate_subtokens` - bool flag indicating if subtokens should be consolidated at the token level
+    /// * `return_special` - bool flag indicating if labels for special tokens should be returned
+    /// * `progress` - callback invoked after each batch with `(batches_done, total_batches)`
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Token>>` containing Tokens with associated labels (for example POS tags) for each input provided
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    ///
+    /// let ner_model = TokenClassificationModel::new(Default::default())?;
+    /// let input = [
+    ///     "My name is Amy. I live in Paris.",
+    ///     "Paris is a city in France.",
+    /// ];
+    /// let output = ner_model.predict_with_progress(&input, true, true, |done, total| {
+    ///     println!("{done}/{total} batches processed")
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_with_progress<S, P>(
+        &self,
+        input: &[S],
+        consolidate_sub_tokens: bool,
+        return_special: bool,
+        mut progress: P,
+    ) -> Vec<Vec<Token>>
+    where
+        S: AsRef<str>,
+        P: FnMut(usize, usize),
+    {
+        let mut tokens = self.predict_tokens(
+            input,
+            return_special,
+            Some(&mut |batches_done, total_batches| {
+                progress(batches_done, total_batches);
+                true
+            }),
+            None,
+            |token, _, _, _, _| token,
+        );
+
+        if consolidate_sub_tokens {
+            self.consolidate_tokens(&mut tokens, &self.label_aggregation_function);
+        }
+        tokens
+    }
+
+    /// Classify tokens in a text sequence, stopping early if cancellation is requested. The cancellation flag
+    /// is checked before processing each batch, so that a batch already started runs to completion.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    /// * `consolidate_subtokens` - bool flag indicating if subtokens should be consolidated at the token level
+    /// * `return_special` - bool flag indicating if labels for special tokens should be returned
+    /// * `cancel` - `&AtomicBool` flag, set to `true` (e.g. from another thread) to cancel the prediction
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Vec<Token>>, RustBertError>` containing Tokens with associated labels for each input provided,
+    /// or `RustBertError::Cancelled` if the prediction was cancelled
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    /// use std::sync::atomic::AtomicBool;
+    /// use std::sync::Arc;
+    ///
+    /// let ner_model = TokenClassificationModel::new(Default::default())?;
+    /// let cancel = Arc::new(AtomicBool::new(false));
+    /// let input = [
+    ///     "My name is Amy. I live in Paris.",
+    ///     "Paris is a city in France.",
+    /// ];
+    /// let output = ner_model.predict_cancellable(&input, true, true, &cancel)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_cancellable<S>(
+        &self,
+        input: &[S],
+        consolidate_sub_tokens: bool,
+        return_special: bool,
+        cancel: &AtomicBool,
+    ) -> Result<Vec<Vec<Token>>, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(RustBertError::Cancelled);
+        }
+        let mut tokens = self.predict_tokens(
+            input,
+            return_special,
+            Some(&mut |_, _| !cancel.load(Ordering::Relaxed)),
+            None,
+            |token, _, _, _, _| token,
+        );
+        if cancel.load(Ordering::Relaxed) {
+            return Err(RustBertError::Cancelled);
+        }
+
+        if consolidate_sub_tokens {
+            self.consolidate_tokens(&mut tokens, &self.label_aggregation_function);
+        }
+        Ok(tokens)
+    }
+
+    /// Classify tokens in a text sequence, measuring the time spent tokenizing the inputs, in the forward pass of the
+    /// model and in post-processing. The measurements are only taken by this method, `predict` is not instrumented.
+    /// On CUDA devices, the pipeline waits for the completion of each forward pass, which may slightly slow down the prediction.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    /// * `consolidate_subtokens` - bool flag indicating if subtokens should be consolidated at the token level
+    /// * `return_special` - bool flag indicating if labels for special tokens should be returned
+    ///
+    /// # Returns
+    ///
+    /// * `(Vec<Vec<Token>>, Timings)` Tokens with associated labels for each input provided, and the time spent in each stage
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    ///
+    /// let ner_model = TokenClassificationModel::new(Default::default())?;
+    /// let input = ["My name is Amy. I live in Paris."];
+    /// let (output, timings) = ner_model.predict_with_timings(&input, true, false);
+    /// println!("forward pass: {:?}", timings.forward);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_with_timings<S>(
+        &self,
+        input: &[S],
+        consolidate_sub_tokens: bool,
+        return_special: bool,
+    ) -> (Vec<Vec<Token>>, Timings)
+    where
+        S: AsRef<str>,
+    {
+        let mut timings = Timings::default();
+        let mut tokens = self.predict_tokens(
+            input,
+            return_special,
+            None,
+            Some(&mut timings),
+            |token, _, _, _, _| token,
+        );
+
+        if consolidate_sub_tokens {
+            let consolidation_start = Instant::now();
+            self.consolidate_tokens(&mut tokens, &self.label_aggregation_function);
+            timings.postprocess += consolidation_start.elapsed();
+        }
+        (tokens, timings)
+    }
+
+    /// Classify tokens from pre-tokenized inputs, skipping the tokenization step. The token ids should not
+    /// contain special tokens, which are added by the pipeline. As the original text is not available, the returned
+    /// tokens have no offsets and their text is reconstructed by decoding the token ids.
+    /// Sub-tokens are not consolidated since the tokenization masks are not available.
+    /// The token ids are plain integers: the input tensors are built on the device of the model, so inputs
+    /// cannot end up on a different device than the model weights.
+    ///
+    /// # Arguments
+    ///
+    /// * `token_ids` - `&[Vec<i64>]` Token ids (without special tokens) of each input, as produced by the model tokenizer.
+    /// * `return_special` - bool flag indicating if labels for special tokens should be returned
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Token>>` containing Tokens with associated labels for each input provided
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    ///
+    /// let ner_model = TokenClassificationModel::new(Default::default())?;
+    /// let token_ids = [vec![1422, 1271, 1110, 10280, 119]];
+    /// let output = ner_model.predict_from_token_ids(&token_ids, false);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_from_token_ids(
+        &self,
+        token_ids: &[Vec<i64>],
+        return_special: bool,
+    ) -> Vec<Vec<Token>> {
+        let features = token_ids
+            .iter()
+            .enumerate()
+            .flat_map(|(example_index, ids)| {
+                let encoded_input = TokenIdsWithOffsets {
+                    ids: ids.clone(),
+                    offsets: vec![None; ids.len()],
+                    reference_offsets: vec![vec![]; ids.len()],
+                    masks: vec![Mask::None; ids.len()],
+                };
+                self.generate_features_from_encoded(encoded_input, example_index)
+            })
+            .collect();
+        let original_chars = vec![vec![]; token_ids.len()];
+        self.predict_features(
+            features,
+            &original_chars,
+            return_special,
+            None,
+            None,
+            |token, _, _, _, _| token,
+        )
+    }
+
+    /// Classify tokens in a text sequence, returning the probability of every label for each token.
+    /// Sub-tokens are not consolidated, allowing custom decoding strategies on top of the model output.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    /// * `return_special` - bool flag indicating if labels for special tokens should be returned
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<TokenLabelScores>>` containing Tokens with the probability distribution over labels, for each input provided
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    ///
+    /// let ner_model = TokenClassificationModel::new(Default::default())?;
+    /// let input = [
+    ///     "My name is Amy. I live in Paris.",
+    ///     "Paris is a city in France.",
+    /// ];
+    /// let output = ner_model.predict_label_scores(&input, false);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_label_scores<S>(
+        &self,
+        input: &[S],
+        return_special: bool,
+    ) -> Vec<Vec<TokenLabelScores>>
+    where
+        S: AsRef<str>,
+    {
+        self.predict_tokens(
+            input,
+            return_special,
+            None,
+            None,
+            |token, score, _, sentence_idx, position_idx| TokenLabelScores {
+                token,
+                label_scores: score
+                    .get(sentence_idx)
+                    .get(position_idx)
+                    .iter::<f64>()
+                    .unwrap()
+                    .collect::<Vec<f64>>(),
+            },
+        )
+    }
+
+    /// Classify tokens in a text sequence, returning the raw logits of every label for each token (before softmax).
+    /// This allows applying custom calibration (for example temperature scaling) to the model output.
+    /// Sub-tokens are not consolidated.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    /// * `return_special` - bool flag indicating if labels for special tokens should be returned
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<TokenLabelLogits>>` containing Tokens with the logits over labels, for each input provided
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    ///
+    /// let ner_model = TokenClassificationModel::new(Default::default())?;
+    /// let input = [
+    ///     "My name is Amy. I live in Paris.",
+    ///     "Paris is a city in France.",
+    /// ];
+    /// let output = ner_model.predict_logits(&input, false);
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn predict<S>(
+    pub fn predict_logits<S>(&self, input: &[S], return_special: bool) -> Vec<Vec<TokenLabelLogits>>
+    where
+        S: AsRef<str>,
+    {
+        self.predict_tokens(
+            input,
+            return_special,
+            None,
+            None,
+            |token, _, logits, sentence_idx, position_idx| TokenLabelLogits {
+                token,
+                label_logits: logits
+                    .get(sentence_idx)
+                    .get(position_idx)
+                    .iter::<f64>()
+                    .unwrap()
+                    .collect::<Vec<f64>>(),
+            },
+        )
+    }
+
+    /// Classify tokens in a text sequence, returning the Shannon entropy of the label distribution of each token.
+    /// High entropies indicate tokens for which the model is uncertain, for example to select inputs for labelling
+    /// in active learning. Sub-tokens are not consolidated.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    /// * `return_special` - bool flag indicating if labels for special tokens should be returned
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<TokenEntropy>>` containing Tokens with the entropy of their label distribution, for each input provided
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    ///
+    /// let ner_model = TokenClassificationModel::new(Default::default())?;
+    /// let input = [
+    ///     "My name is Amy. I live in Paris.",
+    ///     "Paris is a city in France.",
+    /// ];
+    /// let output = ner_model.predict_entropy(&input, false);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_entropy<S>(&self, input: &[S], return_special: bool) -> Vec<Vec<TokenEntropy>>
+    where
+        S: AsRef<str>,
+    {
+        self.predict_tokens(
+            input,
+            return_special,
+            None,
+            None,
+            |token, _, logits, sentence_idx, position_idx| TokenEntropy {
+                token,
+                entropy: label_entropy(&logits.get(sentence_idx).get(position_idx)),
+            },
+        )
+    }
+
+    /// Returns the hidden states and attention weights of all layers of the model for each input.
+    /// These are only kept if `output_hidden_states` and/or `output_attentions` are set in the
+    /// `TokenClassificationConfig` (BERT and RoBERTa models only), the default prediction path does not store them.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to process.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<TokenClassificationHiddenStates>` containing the hidden states and attention weights for each input provided
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::token_classification::{TokenClassificationConfig, TokenClassificationModel};
+    ///
+    /// let ner_model = TokenClassificationModel::new(TokenClassificationConfig {
+    ///     output_hidden_states: true,
+    ///     output_attentions: true,
+    ///     ..Default::default()
+    /// })?;
+    /// let output = ner_model.predict_hidden_states(&["My name is Amy. I live in Paris."]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_hidden_states<S>(&self, input: &[S]) -> Vec<TokenClassificationHiddenStates>
+    where
+        S: AsRef<str>,
+    {
+        input
+            .iter()
+            .enumerate()
+            .map(|(example_index, example)| {
+                let mut features = self.get_features(example.as_ref(), example_index);
+                if features.is_empty() {
+                    return TokenClassificationHiddenStates {
+                        all_hidden_states: vec![],
+                        all_attentions: vec![],
+                    };
+                }
+                let (input_ids, attention_masks) = pad_features(
+                    &mut features,
+                    self.special_token_ids.pad,
+                    self.var_store.device(),
+                );
+                let (all_hidden_states, all_attentions) = no_grad(|| {
+                    self.token_sequence_classifier
+                        .forward_hidden_states(Some(&input_ids), Some(&attention_masks))
+                });
+                TokenClassificationHiddenStates {
+                    all_hidden_states: all_hidden_states.unwrap_or_default(),
+                    all_attentions: all_attentions.unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+
+    fn predict_tokens<S, T, F>(
         &self,
         input: &[S],
-        consolidate_sub_tokens: bool,
         return_special: bool,
-    ) -> Vec<Vec<Token>>
+        on_batch: Option<&mut dyn FnMut(usize, usize) -> bool>,
+        mut timings: Option<&mut Timings>,
+        build_output: F,
+    ) -> Vec<Vec<T>>
     where
         S: AsRef<str>,
+        F: FnMut(Token, &Tensor, &Tensor, i64, i64) -> T,
     {
-        let mut features: Vec<InputFeature> = input
+        let tokenize_start = timings.as_ref().map(|_| Instant::now());
+        let features: Vec<InputFeature> = input
             .iter()
             .enumerate()
-            .flat_map(|(example_index, example)| self.generate_features(example, example_index))
+            .flat_map(|(example_index, example)| self.get_features(example.as_ref(), example_index))
             .collect();
+        let original_chars = input
+            .iter()
+            .map(|example| example.as_ref().chars().collect::<Vec<char>>())
+            .collect::<Vec<Vec<char>>>();
+        if let (Some(timings), Some(tokenize_start)) = (timings.as_mut(), tokenize_start) {
+            timings.tokenize += tokenize_start.elapsed();
+        }
+        self.predict_features(
+            features,
+            &original_chars,
+            return_special,
+            on_batch,
+            timings,
+            build_output,
+        )
+    }
 
-        let mut example_tokens_map: Vec<Vec<Token>> = vec![Vec::new(); input.len()];
-        let mut start = 0usize;
+    fn predict_features<T, F>(
+        &self,
+        features: Vec<InputFeature>,
+        original_chars: &[Vec<char>],
+        return_special: bool,
+        mut on_batch: Option<&mut dyn FnMut(usize, usize) -> bool>,
+        mut timings: Option<&mut Timings>,
+        mut build_output: F,
+    ) -> Vec<Vec<T>>
+    where
+        F: FnMut(Token, &Tensor, &Tensor, i64, i64) -> T,
+    {
+        // `on_batch` is called after each batch with `(batches_done, total_batches)`, processing stops if it returns false
+        // If `timings` is provided, the time spent in the forward pass and in post-processing is added to it
+        let mut example_tokens_map: Vec<Vec<T>> =
+            (0..original_chars.len()).map(|_| Vec::new()).collect();
         let len_features = features.len();
+        // Features are sorted by length so that each batch groups inputs of similar lengths and is padded minimally.
+        // Outputs are collected per feature and restored in the original order once all batches are processed.
+        let mut feature_positions = (0..len_features).collect::<Vec<usize>>();
+        feature_positions.sort_by_key(|&position| features[position].input_ids.len());
+        let example_indices = features
+            .iter()
+            .map(|feature| feature.example_index)
+            .collect::<Vec<usize>>();
+        let mut features = {
+            let mut features = features.into_iter().map(Some).collect::<Vec<_>>();
+            feature_positions
+                .iter()
+                .map(|&position| features[position].take().unwrap())
+                .collect::<Vec<InputFeature>>()
+        };
+        let mut feature_outputs: Vec<Vec<T>> = (0..len_features).map(|_| Vec::new()).collect();
+        let mut start = 0usize;
+        let total_batches =
+            len_features / self.batch_size + usize::from(len_features % self.batch_size != 0);
+        let mut batches_done = 0usize;
+        debug!(
+            "Classifying {} features from {} inputs in {} batches of up to {} features on {:?}",
+            len_features,
+            original_chars.len(),
+            total_batches,
+            self.batch_size,
+            self.var_store.device()
+        );
 
         while start < len_features {
             let end = start + min(len_features - start, self.batch_size);
 
             no_grad(|| {
+                let forward_start = timings.as_ref().map(|_| Instant::now());
                 let batch_features = &mut features[start..end];
-                let (input_ids, attention_masks) = self.pad_features(batch_features);
-                let output = self.token_sequence_classifier.forward_t(
-                    Some(&input_ids),
-                    Some(&attention_masks),
-                    None,
-                    None,
-                    None,
-                    false,
+                let (input_ids, attention_masks) = pad_features(
+                    batch_features,
+                    self.special_token_ids.pad,
+                    self.var_store.device(),
+                );
+                trace!(
+                    "Processing batch {}/{} of shape {:?}",
+                    batches_done + 1,
+                    total_batches,
+                    input_ids.size()
                 );
-                let score = output.exp()
-                    / output
-                        .exp()
-                        .sum_dim_intlist([-1].as_slice(), true, Kind::Float);
+                // Single sequences: all tokens belong to the first segment
+                let token_type_ids = input_ids.zeros_like();
+                let output = self
+                    .token_sequence_classifier
+                    .forward(&input_ids, &attention_masks, Some(&token_type_ids))
+                    .unwrap_or_else(|error| {
+                        panic!("Error in the token classification forward pass: {error}")
+                    });
+                let postprocess_start = match (timings.as_mut(), forward_start) {
+                    (Some(timings), Some(forward_start)) => {
+                        // CUDA kernels run asynchronously: wait for the forward pass to complete
+                        if let Device::Cuda(device_index) = self.var_store.device() {
+                            tch::Cuda::synchronize(device_index as i64);
+                        }
+                        timings.forward += forward_start.elapsed();
+                        Some(Instant::now())
+                    }
+                    _ => None,
+                };
+                let score = if self.log_probabilities {
+                    label_log_probabilities(&output)
+                } else {
+                    label_probabilities(&output)
+                };
                 let label_indices = score.argmax(-1, true);
                 for sentence_idx in 0..label_indices.size()[0] {
                     let labels = label_indices.get(sentence_idx);
-                    let feature = &features[sentence_idx as usize];
+                    let feature = &features[start + sentence_idx as usize];
+                    let feature_output =
+                        &mut feature_outputs[feature_positions[start + sentence_idx as usize]];
                     let sentence_reference_flag = &feature.reference_feature;
-                    let original_chars = input[feature.example_index]
-                        .as_ref()
-                        .chars()
-                        .collect::<Vec<char>>();
+                    let original_chars = &original_chars[feature.example_index];
                     let mut word_idx: u16 = 0;
                     for position_idx in sentence_reference_flag
                         .iter()
@@ -917,69 +2467,50 @@ impl TokenClassificationModel {
                         if !(mask == Mask::Continuation) {
                             word_idx += 1;
                         }
-                        let token = {
-                            self.decode_token(
-                                &original_chars,
-                                feature,
-                                &input_ids,
-                                &labels,
-                                &score,
-                                sentence_idx,
-                                position_idx as i64,
-                                word_idx,
-                            )
+                        let token = match self.decode_token(
+                            original_chars,
+                            feature,
+                            &input_ids,
+                            &labels,
+                            &score,
+                            sentence_idx,
+                            position_idx as i64,
+                            word_idx,
+                        ) {
+                            Some(token) => token,
+                            None => continue,
                         };
-                        example_tokens_map[feature.example_index].push(token);
+                        feature_output.push(build_output(
+                            token,
+                            &score,
+                            &output,
+                            sentence_idx,
+                            position_idx as i64,
+                        ));
                     }
                 }
+                if let (Some(timings), Some(postprocess_start)) =
+                    (timings.as_mut(), postprocess_start)
+                {
+                    timings.postprocess += postprocess_start.elapsed();
+                }
             });
             start = end;
+            batches_done += 1;
+            if let Some(on_batch) = on_batch.as_mut() {
+                if !on_batch(batches_done, total_batches) {
+                    debug!(
+                        "Prediction stopped after {}/{} batches",
+                        batches_done, total_batches
+                    );
+                    break;
+                }
+            }
         }
-        let mut tokens = example_tokens_map;
-
-        if consolidate_sub_tokens {
-            self.consolidate_tokens(&mut tokens, &self.label_aggregation_function);
-        }
-        tokens
-    }
-
-    fn pad_features(&self, features: &mut [InputFeature]) -> (Tensor, Tensor) {
-        let max_len = features
-            .iter()
-            .map(|feature| feature.input_ids.len())
-            .max()
-            .unwrap();
-
-        let attention_masks = features
-            .iter()
-            .map(|feature| &feature.input_ids)
-            .map(|input| {
-                let mut attention_mask = Vec::with_capacity(max_len);
-                attention_mask.resize(input.len(), 1);
-                attention_mask.resize(max_len, 0);
-                attention_mask
-            })
-            .map(|input| Tensor::of_slice(&(input)))
-            .collect::<Vec<_>>();
-
-        let padding_index = self
-            .tokenizer
-            .get_pad_id()
-            .expect("Only tokenizers with a padding index can be used for token classification");
-        for feature in features.iter_mut() {
-            feature.input_ids.resize(max_len, padding_index);
-            feature.offsets.resize(max_len, None);
-            feature.reference_feature.resize(max_len, false);
+        for (feature_output, example_index) in feature_outputs.into_iter().zip(example_indices) {
+            example_tokens_map[example_index].extend(feature_output);
         }
-
-        let padded_input_ids = features
-            .iter()
-            .map(|input| Tensor::of_slice(input.input_ids.as_slice()))
-            .collect::<Vec<_>>();
-
-        let input_ids = Tensor::stack(&padded_input_ids, 0).to(self.var_store.device());
-        let attention_masks = Tensor::stack(&attention_masks, 0).to(self.var_store.device());
-        (input_ids, attention_masks)
+        example_tokens_map
     }
 
     fn decode_token(
@@ -992,8 +2523,18 @@ impl TokenClassificationModel {
         sentence_idx: i64,
         position_idx: i64,
         word_index: u16,
-    ) -> Token {
+    ) -> Option<Token> {
         let label_id = labels.int64_value(&[position_idx]);
+        let label = match self.label_mapping.get(&label_id) {
+            Some(label) => label.to_owned(),
+            None => {
+                warn!(
+                    "Predicted label index {} is not in the label mapping, skipping token",
+                    label_id
+                );
+                return None;
+            }
+        };
         let token_id = input_tensor.int64_value(&[sentence_idx, position_idx]);
 
         let offsets = &sentence_tokens.offsets[position_idx as usize];
@@ -1021,8 +2562,10 @@ impl TokenClassificationModel {
                 ),
             },
             Some(offsets) => {
+                // Offsets index characters (not bytes) of the input, clamped to the input length
                 let (start_char, end_char) = (offsets.begin as usize, offsets.end as usize);
                 let end_char = min(end_char, original_sentence_chars.len());
+                let start_char = min(start_char, end_char);
                 let text = original_sentence_chars[start_char..end_char]
                     .iter()
                     .collect();
@@ -1030,21 +2573,23 @@ impl TokenClassificationModel {
             }
         };
 
-        Token {
+        Some(Token {
             text,
             score: score.double_value(&[sentence_idx, position_idx, label_id]),
-            label: self
-                .label_mapping
-                .get(&label_id)
-                .expect("Index out of vocabulary bounds.")
-                .to_owned(),
+            label,
             label_index: label_id,
-            sentence: sentence_idx as usize,
+            sentence: sentence_tokens.example_index,
             index: position_idx as u16,
+            index_end: position_idx as u16 + 1,
             word_index,
             offset: offsets.to_owned(),
             mask: sentence_tokens.mask[position_idx as usize],
-        }
+        })
+    }
+
+    /// Consolidates sub-tokens at the word level using the label aggregation function of the model
+    pub(crate) fn consolidate_sub_tokens(&self, tokens: &mut Vec<Vec<Token>>) {
+        self.consolidate_tokens(tokens, &self.label_aggregation_function);
     }
 
     fn consolidate_tokens(
@@ -1063,6 +2608,7 @@ impl TokenClassificationModel {
                         self.consolidate_labels(sub_tokens, label_aggregation_function);
                     let sentence = (sub_tokens[0]).sentence;
                     let index = (sub_tokens[0]).index;
+                    let index_end = sub_tokens.last().unwrap().index_end;
                     let word_index = (sub_tokens[0]).word_index;
                     let offset_start = sub_tokens
                         .first()
@@ -1084,14 +2630,22 @@ impl TokenClassificationModel {
                         None
                     };
                     let mut text = String::new();
-                    let mut score = 1f64;
+                    let mut score = if self.log_probabilities { 0f64 } else { 1f64 };
                     for current_sub_token in sub_tokens.iter() {
                         text.push_str(current_sub_token.text.as_str());
-                        score *= if current_sub_token.label_index == label_index {
-                            current_sub_token.score
+                        if self.log_probabilities {
+                            score += if current_sub_token.label_index == label_index {
+                                current_sub_token.score
+                            } else {
+                                (-current_sub_token.score.exp()).ln_1p()
+                            };
                         } else {
-                            1.0 - current_sub_token.score
-                        };
+                            score *= if current_sub_token.label_index == label_index {
+                                current_sub_token.score
+                            } else {
+                                1.0 - current_sub_token.score
+                            };
+                        }
                     }
                     let token = Token {
                         text,
@@ -1100,6 +2654,7 @@ impl TokenClassificationModel {
                         label_index,
                         sentence,
                         index,
+                        index_end,
                         word_index,
                         offset,
                         mask: Default::default(),
@@ -1143,7 +2698,486 @@ impl TokenClassificationModel {
                     .map(|((label_index, label), _)| (label_index, label.to_owned()))
                     .unwrap()
             }
+            LabelAggregationOption::Max => {
+                let token = tokens
+                    .iter()
+                    .max_by_key(|token| OrderedFloat(token.score))
+                    .unwrap();
+                (token.label_index, token.label.clone())
+            }
             LabelAggregationOption::Custom(function) => function(tokens),
         }
     }
 }
+
+/// Pads a batch of features to the length of its longest feature (and not to the maximum length of the model),
+/// returning the input ids and attention mask tensors of shape (*batch size*, *longest feature length*)
+fn pad_features(
+    features: &mut [InputFeature],
+    padding_index: i64,
+    device: Device,
+) -> (Tensor, Tensor) {
+    let max_len = features
+        .iter()
+        .map(|feature| feature.input_ids.len())
+        .max()
+        .unwrap();
+
+    let attention_masks = features
+        .iter()
+        .map(|feature| &feature.input_ids)
+        .map(|input| {
+            let mut attention_mask = Vec::with_capacity(max_len);
+            attention_mask.resize(input.len(), 1);
+            attention_mask.resize(max_len, 0);
+            attention_mask
+        })
+        .map(|input| Tensor::of_slice(&(input)))
+        .collect::<Vec<_>>();
+
+    for feature in features.iter_mut() {
+        feature.input_ids.resize(max_len, padding_index);
+        feature.offsets.resize(max_len, None);
+        feature.reference_feature.resize(max_len, false);
+    }
+
+    let padded_input_ids = features
+        .iter()
+        .map(|input| Tensor::of_slice(input.input_ids.as_slice()))
+        .collect::<Vec<_>>();
+
+    let input_ids = Tensor::stack(&padded_input_ids, 0).to(device);
+    let attention_masks = Tensor::stack(&attention_masks, 0).to(device);
+    (input_ids, attention_masks)
+}
+
+/// Numerically stable softmax over the label dimension of the token classification logits
+fn label_probabilities(logits: &Tensor) -> Tensor {
+    logits.softmax(-1, Kind::Float)
+}
+
+fn label_log_probabilities(logits: &Tensor) -> Tensor {
+    logits.log_softmax(-1, Kind::Float)
+}
+
+/// Shannon entropy of the label distribution of a single token, computed from its logits
+fn label_entropy(token_logits: &Tensor) -> f64 {
+    let log_probabilities = label_log_probabilities(token_logits);
+    -(log_probabilities.exp() * &log_probabilities)
+        .sum(Kind::Float)
+        .double_value(&[])
+}
+
+/// Least-recently-used cache of input features, keyed on the input text and the maximum sequence length.
+///
+/// Every access stamps the entry with a new generation and appends it to the usage queue, so that recency is
+/// tracked in constant time. Queue items whose generation no longer matches their entry are stale and skipped
+/// (lazily) on eviction.
+struct FeatureCache {
+    capacity: usize,
+    generation: u64,
+    entries: HashMap<(String, usize), (u64, Arc<Vec<InputFeature>>)>,
+    usage_order: VecDeque<(u64, (String, usize))>,
+}
+
+impl FeatureCache {
+    fn new(capacity: usize) -> FeatureCache {
+        FeatureCache {
+            capacity,
+            generation: 0,
+            entries: HashMap::with_capacity(capacity),
+            usage_order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn get(&mut self, key: &(String, usize)) -> Option<Arc<Vec<InputFeature>>> {
+        let generation = self.next_generation();
+        let (entry_generation, features) = self.entries.get_mut(key)?;
+        *entry_generation = generation;
+        let features = features.clone();
+        self.mark_used(generation, key.clone());
+        Some(features)
+    }
+
+    fn insert(&mut self, key: (String, usize), features: Arc<Vec<InputFeature>>) {
+        let generation = self.next_generation();
+        self.entries.insert(key.clone(), (generation, features));
+        self.mark_used(generation, key);
+        while self.entries.len() > self.capacity {
+            match self.usage_order.pop_front() {
+                Some((generation, evicted_key)) => {
+                    if is_current(&self.entries, generation, &evicted_key) {
+                        self.entries.remove(&evicted_key);
+                    }
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn next_generation(&mut self) -> u64 {
+        self.generation += 1;
+        self.generation
+    }
+
+    fn mark_used(&mut self, generation: u64, key: (String, usize)) {
+        self.usage_order.push_back((generation, key));
+        // Drop the stale queue items once they outnumber the live ones, keeping the queue bounded
+        if self.usage_order.len() > 2 * self.capacity.max(1) {
+            let entries = &self.entries;
+            self.usage_order
+                .retain(|(generation, key)| is_current(entries, *generation, key));
+        }
+    }
+}
+
+/// Checks whether a usage queue item still refers to the latest access of its cache entry
+fn is_current(
+    entries: &HashMap<(String, usize), (u64, Arc<Vec<InputFeature>>)>,
+    generation: u64,
+    key: &(String, usize),
+) -> bool {
+    matches!(entries.get(key), Some((entry_generation, _)) if *entry_generation == generation)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = TokenClassificationConfig::default();
+        let _: Box<dyn Send> = Box::new(TokenClassificationModel::new(config));
+    }
+
+    #[test]
+    fn token_classification_option_is_a_backend() {
+        fn assert_backend<B: TokenClassificationBackend>() {}
+        assert_backend::<TokenClassificationOption>();
+    }
+
+    #[test]
+    fn from_dir_reads_lower_case_flag() -> anyhow::Result<()> {
+        let model_dir = tempfile::tempdir()?;
+        std::fs::write(model_dir.path().join("config.json"), "{}")?;
+        std::fs::write(model_dir.path().join("vocab.txt"), "")?;
+        std::fs::write(model_dir.path().join("rust_model.ot"), "")?;
+
+        assert!(!TokenClassificationConfig::from_dir(model_dir.path())?.lower_case);
+
+        std::fs::write(
+            model_dir.path().join("config.json"),
+            r#"{"do_lower_case": true}"#,
+        )?;
+        assert!(TokenClassificationConfig::from_dir(model_dir.path())?.lower_case);
+
+        std::fs::write(
+            model_dir.path().join("tokenizer_config.json"),
+            r#"{"do_lower_case": false}"#,
+        )?;
+        assert!(!TokenClassificationConfig::from_dir(model_dir.path())?.lower_case);
+        assert_eq!(
+            TokenClassificationConfig::from_dir(model_dir.path())?.strip_accents,
+            None
+        );
+
+        std::fs::write(
+            model_dir.path().join("tokenizer_config.json"),
+            r#"{"do_lower_case": true, "strip_accents": false}"#,
+        )?;
+        let config = TokenClassificationConfig::from_dir(model_dir.path())?;
+        assert!(config.lower_case);
+        assert_eq!(config.strip_accents, Some(false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn special_token_ids_from_configuration() -> anyhow::Result<()> {
+        let model_dir = tempfile::tempdir()?;
+        let vocab_path = model_dir.path().join("vocab.txt");
+        let config_path = model_dir.path().join("config.json");
+        std::fs::write(&vocab_path, "[PAD]\n[UNK]\n[CLS]\n[SEP]\n[MASK]\nhello\n")?;
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::Bert,
+            vocab_path.to_str().unwrap(),
+            None,
+            false,
+            None,
+            None,
+        )?;
+
+        std::fs::write(&config_path, "{}")?;
+        let (special_token_ids, special_token_remap) =
+            resolve_special_token_ids(&tokenizer, &config_path)?;
+        assert_eq!(
+            special_token_ids,
+            SpecialTokenIds {
+                cls: Some(2),
+                sep: Some(3),
+                pad: 0
+            }
+        );
+        assert!(special_token_remap.is_empty());
+
+        std::fs::write(&config_path, r#"{"cls_token_id": 10, "pad_token_id": 7}"#)?;
+        let (special_token_ids, special_token_remap) =
+            resolve_special_token_ids(&tokenizer, &config_path)?;
+        assert_eq!(
+            special_token_ids,
+            SpecialTokenIds {
+                cls: Some(10),
+                sep: Some(3),
+                pad: 7
+            }
+        );
+        assert_eq!(special_token_remap, vec![(2, 10)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn classifier_incompatible_with_label_mapping_is_rejected() -> anyhow::Result<()> {
+        use crate::bert::BertConfig;
+        use safetensors::tensor::TensorView;
+        use safetensors::Dtype;
+
+        let model_dir = tempfile::tempdir()?;
+        let model_config = |num_labels: usize| {
+            let id2label = (0..num_labels)
+                .map(|label_id| format!(r#""{label_id}": "LABEL_{label_id}""#))
+                .collect::<Vec<String>>()
+                .join(", ");
+            format!(
+                r#"{{"hidden_act": "gelu", "attention_probs_dropout_prob": 0.1, "hidden_dropout_prob": 0.1,
+                "hidden_size": 4, "initializer_range": 0.02, "intermediate_size": 8, "max_position_embeddings": 16,
+                "num_attention_heads": 1, "num_hidden_layers": 1, "type_vocab_size": 2, "vocab_size": 6,
+                "id2label": {{{id2label}}}}}"#
+            )
+        };
+        std::fs::write(
+            model_dir.path().join("vocab.txt"),
+            "[PAD]\n[UNK]\n[CLS]\n[SEP]\n[MASK]\nhello\n",
+        )?;
+
+        //    Weights of a model with a 2 labels classification head, saved in the safetensors format
+        std::fs::write(model_dir.path().join("config.json"), model_config(2))?;
+        let var_store = VarStore::new(Device::Cpu);
+        BertForTokenClassification::new(
+            var_store.root(),
+            &BertConfig::from_file(model_dir.path().join("config.json")),
+        )?;
+        let tensors = var_store
+            .variables()
+            .into_iter()
+            .map(|(name, tensor)| {
+                let data = Vec::<f32>::try_from(&tensor.flatten(0, -1))?
+                    .iter()
+                    .flat_map(|value| value.to_le_bytes())
+                    .collect::<Vec<u8>>();
+                let shape = tensor.size().iter().map(|dim| *dim as usize).collect();
+                Ok((name, shape, data))
+            })
+            .collect::<Result<Vec<(String, Vec<usize>, Vec<u8>)>, tch::TchError>>()?;
+        let views = tensors
+            .iter()
+            .map(|(name, shape, data)| {
+                (
+                    name.as_str(),
+                    TensorView::new(Dtype::F32, shape.clone(), data).unwrap(),
+                )
+            })
+            .collect::<Vec<(&str, TensorView)>>();
+        std::fs::write(
+            model_dir.path().join("model.safetensors"),
+            safetensors::serialize(views, &None).unwrap(),
+        )?;
+
+        let mut config = TokenClassificationConfig::from_dir(model_dir.path())?;
+        config.device = Device::Cpu;
+        assert!(TokenClassificationModel::new(config).is_ok());
+
+        //    The configuration defines 3 labels
+        std::fs::write(model_dir.path().join("config.json"), model_config(3))?;
+        let mut config = TokenClassificationConfig::from_dir(model_dir.path())?;
+        config.device = Device::Cpu;
+        assert!(matches!(
+            TokenClassificationModel::new(config),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn labels_file_maps_line_index_to_label_id() -> anyhow::Result<()> {
+        let model_dir = tempfile::tempdir()?;
+        let labels_path = model_dir.path().join("labels.txt");
+
+        std::fs::write(&labels_path, "O\nB-PER\r\nI-PER\n\n")?;
+        let label_mapping = read_labels_file(&labels_path)?;
+        assert_eq!(label_mapping.len(), 3);
+        assert_eq!(label_mapping[&0], "O");
+        assert_eq!(label_mapping[&1], "B-PER");
+        assert_eq!(label_mapping[&2], "I-PER");
+
+        std::fs::write(&labels_path, "O\n\nB-PER\n")?;
+        assert!(read_labels_file(&labels_path).is_err());
+
+        std::fs::write(&labels_path, "\n")?;
+        assert!(read_labels_file(&labels_path).is_err());
+
+        std::fs::write(model_dir.path().join("config.json"), "{}")?;
+        std::fs::write(model_dir.path().join("vocab.txt"), "")?;
+        std::fs::write(model_dir.path().join("rust_model.ot"), "")?;
+        assert!(TokenClassificationConfig::from_dir(model_dir.path())?
+            .labels_resource
+            .is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn model_type_mismatch_is_rejected() -> anyhow::Result<()> {
+        let model_dir = tempfile::tempdir()?;
+        let config_path = model_dir.path().join("config.json");
+
+        std::fs::write(&config_path, r#"{"model_type": "albert"}"#)?;
+        assert!(check_model_type(ModelType::Albert, &config_path).is_ok());
+        assert!(matches!(
+            check_model_type(ModelType::Bert, &config_path),
+            Err(RustBertError::InvalidConfigurationError(_))
+        ));
+
+        std::fs::write(&config_path, r#"{"model_type": "roberta"}"#)?;
+        assert!(check_model_type(ModelType::XLMRoberta, &config_path).is_ok());
+
+        std::fs::write(&config_path, r#"{"model_type": "xlm-roberta"}"#)?;
+        assert!(check_model_type(ModelType::XLMRoberta, &config_path).is_ok());
+
+        std::fs::write(&config_path, "{}")?;
+        assert!(check_model_type(ModelType::Bert, &config_path).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn label_probabilities_large_logits() {
+        let logits =
+            Tensor::of_slice(&[1000f32, 0.0, -1000.0, 500.0, 500.0, 500.0]).view((1, 2, 3));
+
+        let probabilities = label_probabilities(&logits);
+
+        for position in 0..2 {
+            let mut total = 0f64;
+            for label in 0..3 {
+                let probability = probabilities.double_value(&[0, position, label]);
+                assert!(probability.is_finite());
+                total += probability;
+            }
+            assert!((total - 1.0).abs() < 1e-6);
+        }
+        assert!((probabilities.double_value(&[0, 0, 0]) - 1.0).abs() < 1e-6);
+        assert!((probabilities.double_value(&[0, 1, 0]) - 1.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn label_log_probabilities_large_logits() {
+        let logits = Tensor::of_slice(&[1000f32, 0.0, -1000.0]).view((1, 1, 3));
+
+        let log_probabilities = label_log_probabilities(&logits);
+
+        assert!(log_probabilities.double_value(&[0, 0, 0]).abs() < 1e-6);
+        assert!((log_probabilities.double_value(&[0, 0, 1]) + 1000.0).abs() < 1e-3);
+        assert!((log_probabilities.double_value(&[0, 0, 2]) + 2000.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn label_entropy_bounds() {
+        let certain = Tensor::of_slice(&[1000f32, 0.0, -1000.0]);
+        let uniform = Tensor::of_slice(&[2f32, 2.0, 2.0]);
+
+        assert!(label_entropy(&certain).abs() < 1e-6);
+        assert!((label_entropy(&uniform) - 3f64.ln()).abs() < 1e-6);
+        let intermediate = label_entropy(&Tensor::of_slice(&[2f32, 1.0, 0.0]));
+        assert!((intermediate > 0.0) & (intermediate < 3f64.ln()));
+    }
+
+    fn dummy_features(example_index: usize) -> Vec<InputFeature> {
+        vec![InputFeature {
+            input_ids: vec![101, 102],
+            offsets: vec![None, None],
+            mask: vec![Mask::Special, Mask::Special],
+            reference_feature: vec![true, true],
+            example_index,
+        }]
+    }
+
+    #[test]
+    fn batch_padded_to_longest_feature() {
+        let mut features = dummy_features(0);
+        features.push(InputFeature {
+            input_ids: vec![101, 7, 8, 9, 102],
+            offsets: vec![None; 5],
+            mask: vec![
+                Mask::Special,
+                Mask::None,
+                Mask::None,
+                Mask::None,
+                Mask::Special,
+            ],
+            reference_feature: vec![true; 5],
+            example_index: 1,
+        });
+
+        let (input_ids, attention_masks) = pad_features(&mut features, 0, Device::Cpu);
+
+        assert_eq!(input_ids.size(), vec![2, 5]);
+        assert_eq!(attention_masks.size(), vec![2, 5]);
+        assert_eq!(input_ids.int64_value(&[0, 2]), 0);
+        assert_eq!(attention_masks.int64_value(&[0, 1]), 1);
+        assert_eq!(attention_masks.int64_value(&[0, 2]), 0);
+        assert_eq!(attention_masks.int64_value(&[1, 4]), 1);
+        assert!(features.iter().all(|feature| feature.input_ids.len() == 5
+            && feature.offsets.len() == 5
+            && feature.reference_feature.len() == 5));
+    }
+
+    #[test]
+    fn feature_cache_evicts_least_recently_used() {
+        let mut cache = FeatureCache::new(2);
+        let first = ("first".to_string(), 128);
+        let second = ("second".to_string(), 128);
+        let third = ("third".to_string(), 128);
+
+        cache.insert(first.clone(), Arc::new(dummy_features(0)));
+        cache.insert(second.clone(), Arc::new(dummy_features(1)));
+        assert!(cache.get(&first).is_some());
+        cache.insert(third.clone(), Arc::new(dummy_features(2)));
+
+        assert!(cache.get(&first).is_some());
+        assert!(cache.get(&second).is_none());
+        assert!(cache.get(&third).is_some());
+        assert!(cache.get(&("first".to_string(), 64)).is_none());
+    }
+
+    #[test]
+    fn feature_cache_usage_order_stays_bounded() {
+        let mut cache = FeatureCache::new(2);
+        let first = ("first".to_string(), 128);
+        let second = ("second".to_string(), 128);
+
+        cache.insert(first.clone(), Arc::new(dummy_features(0)));
+        cache.insert(second.clone(), Arc::new(dummy_features(1)));
+        for _ in 0..100 {
+            assert!(cache.get(&first).is_some());
+        }
+        assert!(cache.usage_order.len() <= 4);
+
+        cache.insert(("third".to_string(), 128), Arc::new(dummy_features(2)));
+        assert!(cache.get(&first).is_some());
+        assert!(cache.get(&second).is_none());
+    }
+}
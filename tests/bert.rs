@@ -6,17 +6,27 @@ use rust_bert::bert::{
     BertForQuestionAnswering, BertForSequenceClassification, BertForTokenClassification,
     BertModelResources, BertVocabResources,
 };
-use rust_bert::pipelines::common::ModelType;
+use rust_bert::pipelines::common::{ModelType, Pipeline};
 use rust_bert::pipelines::masked_language::{MaskedLanguageConfig, MaskedLanguageModel};
-use rust_bert::pipelines::ner::NERModel;
+use rust_bert::pipelines::ner::{NEREnsemble, NERModel, ScoreAggregation};
 use rust_bert::pipelines::question_answering::{
     QaInput, QuestionAnsweringConfig, QuestionAnsweringModel,
 };
-use rust_bert::resources::{RemoteResource, ResourceProvider};
-use rust_bert::Config;
+use rust_bert::pipelines::token_classification::{
+    TokenClassificationConfig, TokenClassificationModel,
+};
+use rust_bert::resources::{BufferResource, LocalResource, RemoteResource, ResourceProvider};
+use rust_bert::{Config, RustBertError};
 use rust_tokenizers::tokenizer::{BertTokenizer, MultiThreadedTokenizer, TruncationStrategy};
 use rust_tokenizers::vocab::Vocab;
+use rust_tokenizers::Mask;
 use std::collections::HashMap;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+use tch::nn::OptimizerConfig;
 use tch::{nn, no_grad, Device, Tensor};
 
 #[test]
@@ -141,6 +151,37 @@ fn bert_masked_lm_pipeline() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn bert_masked_lm_pipeline_topk() -> anyhow::Result<()> {
+    //    Set-up model
+    let mask_language_model = MaskedLanguageModel::new(Default::default())?;
+
+    //    Define input
+    let input = [
+        "Hello I am a [MASK] student",
+        "Paris is the [MASK] of France. It is [MASK] in Europe.",
+    ];
+
+    //    Run model
+    let output = mask_language_model.predict_topk(input, 3)?;
+
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[0].len(), 1);
+    assert_eq!(output[0][0].position, 5);
+    assert_eq!(output[0][0].tokens.len(), 3);
+    assert_eq!(output[0][0].tokens[0].text, "college");
+    assert_eq!(output[1].len(), 2);
+    assert_eq!(output[1][0].tokens[0].text, "capital");
+    assert_eq!(output[1][1].tokens[0].text, "located");
+    for prediction in output.iter().flatten() {
+        for pair in prediction.tokens.windows(2) {
+            assert!(pair[0].score >= pair[1].score);
+        }
+        assert!(prediction.tokens.iter().all(|token| token.score <= 1.0));
+    }
+    Ok(())
+}
+
 #[test]
 fn bert_for_sequence_classification() -> anyhow::Result<()> {
     //    Resources paths
@@ -419,6 +460,1052 @@ fn bert_pre_trained_ner() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn bert_pre_trained_ner_cpu() -> anyhow::Result<()> {
+    //    Set-up model on CPU, requiring no CUDA runtime
+    let config = TokenClassificationConfig {
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let ner_model = NERModel::new(config)?;
+    assert_eq!(ner_model.device(), Device::Cpu);
+
+    //    Run model
+    let output = ner_model.predict(&["My name is Amy. I live in Paris."]);
+
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0].len(), 2);
+    assert_eq!(output[0][0].word, "Amy");
+    assert_eq!(output[0][0].label, "I-PER");
+    assert_eq!(output[0][1].word, "Paris");
+    assert_eq!(output[0][1].label, "I-LOC");
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_single_input() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input
+    let input = "My name is Amy. I live in Paris.";
+
+    //    Run model
+    let output = ner_model.predict_single(input);
+
+    assert_eq!(output, ner_model.predict(&[input])[0]);
+    assert_eq!(output.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_to_device() -> anyhow::Result<()> {
+    //    Set-up model
+    let mut ner_model = NERModel::new(Default::default())?;
+    let input = ["My name is Amy. I live in Paris."];
+    let expected = ner_model.predict(&input);
+
+    //    Move the model to CPU and back
+    let original_device = ner_model.device();
+    ner_model.to_device(Device::Cpu);
+    assert_eq!(ner_model.device(), Device::Cpu);
+    assert_eq!(ner_model.predict(&input)[0].len(), expected[0].len());
+
+    ner_model.to_device(original_device);
+    assert_eq!(ner_model.device(), original_device);
+    let output = ner_model.predict(&input);
+    assert_eq!(output[0].len(), 2);
+    for (entity, expected_entity) in output[0].iter().zip(expected[0].iter()) {
+        assert_eq!(entity.word, expected_entity.word);
+        assert_eq!(entity.label, expected_entity.label);
+        assert!((entity.score - expected_entity.score).abs() < 1e-4);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_into_buffer() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input
+    let input = [
+        "My name is Amy. I live in Paris.",
+        "Paris is a city in France.",
+    ];
+
+    //    Run model, reusing the output vector
+    let mut output = vec![];
+    ner_model.predict_into(&input, &mut output);
+    let expected = ner_model.predict(&input).concat();
+    assert_eq!(output, expected);
+    assert_eq!(output.len(), 4);
+
+    ner_model.predict_into(&input[1..], &mut output);
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[0].word, "Paris");
+    assert_eq!(output[1].word, "France");
+
+    let owned_input = input
+        .iter()
+        .map(|text| text.to_string())
+        .collect::<Vec<String>>();
+    ner_model.predict_into(&owned_input, &mut output);
+    assert_eq!(output, expected);
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_log_probabilities() -> anyhow::Result<()> {
+    //    Set-up models
+    let ner_model = NERModel::new(Default::default())?;
+    let log_ner_model = NERModel::new(TokenClassificationConfig {
+        log_probabilities: true,
+        ..Default::default()
+    })?;
+
+    //    Define input
+    let input = ["My name is Amy. I live in Paris."];
+
+    //    Run models
+    let output = ner_model.predict(&input);
+    let log_output = log_ner_model.predict(&input);
+
+    assert_eq!(log_output[0].len(), 2);
+    for (entity, log_entity) in output[0].iter().zip(log_output[0].iter()) {
+        assert_eq!(entity.word, log_entity.word);
+        assert_eq!(entity.label, log_entity.label);
+        assert!(log_entity.score <= 0.0);
+        assert!((entity.score.ln() - log_entity.score).abs() < 1e-4);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_entropy() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input
+    let input = ["My name is Amy. I live in Paris."];
+
+    //    Run model
+    let output = ner_model.predict_entropy(&input);
+    let num_labels = ner_model.get_label_mapping().len() as f64;
+
+    assert_eq!(output.len(), 1);
+    assert!(!output[0].is_empty());
+    for token in output[0].iter() {
+        assert!(token.entropy >= 0.0);
+        assert!(token.entropy <= num_labels.ln() + 1e-6);
+    }
+    let amy = output[0]
+        .iter()
+        .find(|token| token.token.text == "Amy")
+        .unwrap();
+    assert_eq!(amy.token.label, "I-PER");
+    assert!(amy.entropy < 0.5);
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_pipeline_trait() -> anyhow::Result<()> {
+    fn predict_with_pipeline<P: Pipeline<Input = str>>(
+        pipeline: &P,
+        input: &[&str],
+    ) -> Vec<P::Output> {
+        pipeline.predict(input)
+    }
+
+    //    Set-up model
+    let mut ner_model = NERModel::new(Default::default())?;
+
+    //    Define input
+    let input = ["My name is Amy. I live in Paris."];
+
+    //    Run model
+    let output = predict_with_pipeline(&ner_model, &input);
+    assert_eq!(output, ner_model.predict(&input));
+
+    Pipeline::to_device(&mut ner_model, Device::Cpu);
+    assert_eq!(Pipeline::device(&ner_model), Device::Cpu);
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_grouped_by_document() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input
+    let input = [
+        ("doc_a", "My name is Amy."),
+        ("doc_b", "Paris is a city in France."),
+        ("doc_a", "I live in Paris."),
+    ];
+
+    //    Run model
+    let output = ner_model.predict_grouped(&input);
+
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[0].0, "doc_a");
+    assert_eq!(output[0].1.len(), 2);
+    assert_eq!(output[0].1[0][0].word, "Amy");
+    assert_eq!(output[0].1[1][0].word, "Paris");
+    assert_eq!(output[1].0, "doc_b");
+    assert_eq!(output[1].1.len(), 1);
+    assert_eq!(output[1].1[0][0].word, "Paris");
+    assert_eq!(output[1].1[0][1].word, "France");
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_grouped_with_confidence() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input
+    let input = [
+        ("doc_a", "My name is Amy."),
+        ("doc_b", "Paris is a city in France."),
+        ("doc_a", "The weather is nice today."),
+    ];
+
+    //    Run model
+    let mean_output = ner_model.predict_grouped_with_confidence(&input, ScoreAggregation::Mean);
+    let min_output = ner_model.predict_grouped_with_confidence(&input, ScoreAggregation::Min);
+
+    assert_eq!(mean_output.len(), 2);
+    assert_eq!(mean_output[0].0, "doc_a");
+    assert_eq!(mean_output[0].1.len(), 2);
+    assert_eq!(mean_output[0].1[0].entities[0].word, "Amy");
+    assert!(mean_output[0].1[1].entities.is_empty());
+    assert_eq!(mean_output[0].1[1].confidence, None);
+    assert_eq!(mean_output[1].0, "doc_b");
+    assert_eq!(
+        mean_output[1].1[0].entities,
+        ner_model.predict(&["Paris is a city in France."])[0]
+    );
+
+    let mean_confidence = mean_output[1].1[0].confidence.unwrap();
+    let min_confidence = min_output[1].1[0].confidence.unwrap();
+    assert!(mean_confidence <= 1.0);
+    assert!(min_confidence > 0.0);
+    assert!(min_confidence <= mean_confidence);
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_multibyte_boundaries() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input with emoji, combining characters and zero-width joiners
+    let input = [
+        "My name is Ame\u{301}lie 👩‍👩‍👧. I live in Paris 🇫🇷.",
+        "🦀🦀 Amy from Zu\u{308}rich 🦀",
+    ];
+
+    //    Run model
+    let tokens = TokenClassificationModel::new(Default::default())?.predict(&input, true, false);
+    let entities = ner_model.predict(&input);
+
+    for (text, sentence_tokens) in input.iter().zip(tokens.iter()) {
+        let num_chars = text.chars().count() as u32;
+        for token in sentence_tokens {
+            if let Some(offset) = token.offset {
+                assert!(offset.begin <= offset.end);
+                assert!(offset.end <= num_chars);
+            }
+        }
+    }
+    for (text, sentence_entities) in input.iter().zip(entities.iter()) {
+        assert!(!sentence_entities.is_empty());
+        for entity in sentence_entities {
+            let surface_form = &text[entity.byte_range(text)];
+            assert!(!surface_form.is_empty());
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_from_pretrained() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::from_pretrained(
+        "dbmdz/bert-large-cased-finetuned-conll03-english",
+        Device::Cpu,
+    )?;
+
+    //    Define input
+    let input = ["My name is Amy. I live in Paris."];
+
+    //    Run model
+    let output = ner_model.predict(&input);
+
+    assert_eq!(output[0].len(), 2);
+    assert_eq!(output[0][0].word, "Amy");
+    assert_eq!(output[0][0].label, "I-PER");
+    assert_eq!(output[0][1].word, "Paris");
+    assert_eq!(output[0][1].label, "I-LOC");
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_num_parameters() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    BERT large (cased) with a 9 labels token classification head
+    let num_parameters = ner_model.num_parameters();
+    assert!(num_parameters > 330_000_000);
+    assert!(num_parameters < 340_000_000);
+    let num_labels = ner_model.get_label_mapping().len() as i64;
+    assert_eq!(num_labels, 9);
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_tokenize_debug() -> anyhow::Result<()> {
+    //    Set-up models
+    let ner_model = NERModel::new(Default::default())?;
+    let short_window_model = NERModel::new(TokenClassificationConfig {
+        max_length: Some(8),
+        window_overlap: Some(2),
+        ..Default::default()
+    })?;
+
+    //    Define input
+    let input = "My name is Amy. I live in Paris.";
+
+    //    Tokenize input
+    let tokenization = ner_model.tokenize_debug(input);
+    let short_window_tokenization = short_window_model.tokenize_debug(input);
+
+    assert_eq!(tokenization.tokens.len(), 10);
+    assert_eq!(tokenization.tokens[3], "Amy");
+    assert_eq!(tokenization.token_ids.len(), tokenization.tokens.len());
+    assert_eq!(tokenization.offsets[3].unwrap().begin, 11);
+    assert_eq!(tokenization.offsets[3].unwrap().end, 14);
+    assert_eq!(tokenization.windows.len(), 1);
+    assert_eq!(tokenization.windows[0].len(), 12);
+    assert_eq!(tokenization.windows[0][1..11], tokenization.token_ids[..]);
+    assert!(!tokenization.exceeds_max_length);
+
+    assert_eq!(short_window_tokenization.tokens, tokenization.tokens);
+    assert_eq!(short_window_tokenization.max_length, 8);
+    assert!(short_window_tokenization.exceeds_max_length);
+    assert!(short_window_tokenization.windows.len() > 1);
+    assert!(short_window_tokenization
+        .windows
+        .iter()
+        .all(|window| window.len() <= 8));
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_ensemble() -> anyhow::Result<()> {
+    //    Set-up ensemble of two identical models
+    let ensemble = NEREnsemble::new(vec![
+        NERModel::new(Default::default())?,
+        NERModel::new(Default::default())?,
+    ])?;
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input
+    let input = ["My name is Amy. I live in Paris."];
+
+    //    Run model
+    let output = ensemble.predict(&input)?;
+    let single_model_output = ner_model.predict(&input);
+
+    assert_eq!(output[0].len(), single_model_output[0].len());
+    for (entity, single_model_entity) in output[0].iter().zip(single_model_output[0].iter()) {
+        assert_eq!(entity.word, single_model_entity.word);
+        assert_eq!(entity.label, single_model_entity.label);
+        assert!((entity.score - single_model_entity.score).abs() < 1e-4);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn ner_ensemble_rejects_incompatible_label_mappings() -> anyhow::Result<()> {
+    let mut config = TokenClassificationConfig::default();
+    let labels_file = tempfile::NamedTempFile::new()?;
+    fs::write(
+        labels_file.path(),
+        "O\nB-PER\nI-PER\nB-ORG\nI-ORG\nB-LOC\nI-LOC\nB-MISC\nSOMETHING-ELSE\n",
+    )?;
+    config.labels_resource = Some(Box::new(LocalResource::from(
+        labels_file.path().to_path_buf(),
+    )));
+
+    let ensemble = NEREnsemble::new(vec![
+        NERModel::new(Default::default())?,
+        NERModel::new(config)?,
+    ]);
+
+    assert!(matches!(
+        ensemble,
+        Err(RustBertError::InvalidConfigurationError(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_detailed() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input
+    let input = ["Asked John Smith about Acme Corp"];
+
+    //    Run model
+    let output = ner_model.predict_detailed(&input);
+
+    assert_eq!(output.len(), 1);
+    assert_eq!(
+        output[0].entities,
+        ner_model.predict_full_entities(&input)[0]
+    );
+    assert!(output[0].token_entities.len() >= output[0].entities.len());
+    for entity in &output[0].entities {
+        assert!(output[0].token_entities.iter().any(|token_entity| {
+            (token_entity.offset.begin >= entity.offset.begin)
+                & (token_entity.offset.end <= entity.offset.end)
+        }));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_timings() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input
+    let input = ["My name is Amy. I live in Paris."];
+
+    //    Run model
+    let (output, timings) = ner_model.predict_with_timings(&input);
+
+    assert_eq!(output, ner_model.predict(&input));
+    assert!(timings.tokenize > Duration::ZERO);
+    assert!(timings.forward > Duration::ZERO);
+    assert!(timings.postprocess > Duration::ZERO);
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_unknown_tokens() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input with characters missing from the vocabulary
+    let input = ["My name is Amy 🦀 Smith. I live in Paris 🗼."];
+
+    //    Run model
+    let output = ner_model.predict(&input);
+
+    assert!(!output[0].is_empty());
+    let chars = input[0].chars().collect::<Vec<char>>();
+    for entity in output[0].iter() {
+        assert_ne!(entity.word, "[UNK]");
+        let span = chars[entity.offset.begin as usize..entity.offset.end as usize]
+            .iter()
+            .collect::<String>();
+        assert_eq!(entity.word, span);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_empty_input() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Run model on an empty input
+    let input: [&str; 0] = [];
+    let output = ner_model.predict(&input);
+
+    assert!(output.is_empty());
+    assert!(ner_model.predict_full_entities(&input).is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_blank_inputs() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input with empty and whitespace-only texts
+    let input = ["", "   \t\n", "My name is Amy. I live in Paris."];
+
+    //    Run model
+    let output = ner_model.predict(&input);
+
+    assert_eq!(output.len(), 3);
+    assert!(output[0].is_empty());
+    assert!(output[1].is_empty());
+    assert_eq!(output[2].len(), 2);
+    assert_eq!(output[2][0].word, "Amy");
+    assert_eq!(output[2][1].word, "Paris");
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_weights_from_buffer() -> anyhow::Result<()> {
+    //    Read the model weights into memory
+    let default_config = TokenClassificationConfig::default();
+    let weights = fs::read(default_config.model_resource.get_local_path()?)?;
+
+    //    Set-up model
+    let config = TokenClassificationConfig {
+        model_resource: Box::new(BufferResource::from(weights)),
+        ..Default::default()
+    };
+    let ner_model = NERModel::new(config)?;
+
+    //    Run model
+    let output = ner_model.predict(&["My name is Amy. I live in Paris."]);
+
+    assert_eq!(output[0].len(), 2);
+    assert_eq!(output[0][0].word, "Amy");
+    assert_eq!(output[0][1].word, "Paris");
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_iterator() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input
+    let input = [
+        "My name is Amy. I live in Paris.",
+        "Paris is a city in France.",
+        "",
+        "Amy lives in France.",
+        "I live in Paris.",
+    ];
+    let reference_output = ner_model.predict(&input);
+
+    //    Run model lazily, with a batch size that does not divide the number of inputs
+    let output = ner_model
+        .predict_iter(input.iter().map(|text| text.to_string()), 2)
+        .collect::<Vec<_>>();
+
+    assert_eq!(output, reference_output);
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_from_receiver() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input
+    let input = [
+        "My name is Amy.",
+        "I live in Paris.",
+        "Paris is a city in France.",
+    ];
+    let reference_output = ner_model.predict(&input);
+
+    //    Send inputs from a producer thread
+    let (sender, receiver) = mpsc::channel();
+    let producer = thread::spawn(move || {
+        for text in input {
+            sender.send(text.to_string()).unwrap();
+            thread::sleep(Duration::from_millis(10));
+        }
+    });
+
+    //    Run model on the received inputs, with a batch size that does not divide the number of inputs
+    let output = ner_model
+        .predict_from_receiver(receiver, 2, Duration::from_millis(100))
+        .collect::<Vec<_>>();
+    producer.join().unwrap();
+
+    assert_eq!(output.len(), input.len());
+    for ((text, entities), (input_text, reference_entities)) in
+        output.iter().zip(input.iter().zip(reference_output.iter()))
+    {
+        assert_eq!(text, input_text);
+        assert_eq!(entities, reference_entities);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_progress() -> anyhow::Result<()> {
+    //    Set-up model with a small batch size
+    let ner_model = NERModel::new(TokenClassificationConfig {
+        batch_size: 2,
+        ..Default::default()
+    })?;
+
+    //    Define input
+    let input = [
+        "My name is Amy. I live in Paris.",
+        "Paris is a city in France.",
+        "Amy lives in France.",
+    ];
+
+    //    Run model
+    let mut progress = vec![];
+    let output =
+        ner_model.predict_with_progress(&input, |done, total| progress.push((done, total)));
+
+    assert_eq!(output, ner_model.predict(&input));
+    assert_eq!(progress, vec![(1, 2), (2, 2)]);
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_cancellation() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input
+    let input = [
+        "My name is Amy. I live in Paris.",
+        "Paris is a city in France.",
+    ];
+
+    //    Run model without cancellation
+    let cancel = Arc::new(AtomicBool::new(false));
+    let output = ner_model.predict_cancellable(&input, &cancel)?;
+    assert_eq!(output, ner_model.predict(&input));
+
+    //    Run model with a cancelled prediction
+    cancel.store(true, Ordering::Relaxed);
+    assert!(matches!(
+        ner_model.predict_cancellable(&input, &cancel),
+        Err(RustBertError::Cancelled)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_shared_across_threads() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = Arc::new(NERModel::new(Default::default())?);
+
+    //    Define input
+    let input = [
+        "My name is Amy. I live in Paris.",
+        "Paris is a city in France.",
+    ];
+    let reference_output = ner_model.predict(&input);
+
+    //    Run model concurrently
+    let handles = (0..4)
+        .map(|_| {
+            let ner_model = Arc::clone(&ner_model);
+            thread::spawn(move || ner_model.predict(&input))
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        let output = handle.join().unwrap();
+        assert_eq!(output.len(), reference_output.len());
+        for (entities, reference_entities) in output.iter().zip(reference_output.iter()) {
+            assert_eq!(entities.len(), reference_entities.len());
+            for (entity, reference_entity) in entities.iter().zip(reference_entities.iter()) {
+                assert_eq!(entity.word, reference_entity.word);
+                assert_eq!(entity.label, reference_entity.label);
+                assert!((entity.score - reference_entity.score).abs() < 1e-4);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_half_precision() -> anyhow::Result<()> {
+    //    Half precision inference is only supported on GPU
+    if !Device::cuda_if_available().is_cuda() {
+        return Ok(());
+    }
+
+    //    Set-up model
+    let mut ner_model = NERModel::new(Default::default())?;
+
+    //    Define input
+    let input = [
+        "My name is Amy. I live in Paris.",
+        "Paris is a city in France.",
+    ];
+    let reference_output = ner_model.predict(&input);
+
+    //    Run model in half precision
+    ner_model.half();
+    let output = ner_model.predict(&input);
+
+    assert_eq!(output.len(), reference_output.len());
+    for (entities, reference_entities) in output.iter().zip(reference_output.iter()) {
+        assert_eq!(entities.len(), reference_entities.len());
+        for (entity, reference_entity) in entities.iter().zip(reference_entities.iter()) {
+            assert_eq!(entity.word, reference_entity.word);
+            assert_eq!(entity.label, reference_entity.label);
+            assert!((entity.score - reference_entity.score).abs() < 1e-2);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_sliding_window() -> anyhow::Result<()> {
+    //    Set-up model with short overlapping windows
+    let ner_model = NERModel::new(TokenClassificationConfig {
+        max_length: Some(16),
+        window_overlap: Some(6),
+        ..Default::default()
+    })?;
+
+    //    Define input longer than a single window
+    let sentence = "My name is Amy. I live in Paris. ";
+    let input = [sentence.repeat(4)];
+
+    //    Run model
+    let output = ner_model.predict(&input);
+
+    assert_eq!(output[0].len(), 8);
+    for (repetition, entities) in output[0].chunks(2).enumerate() {
+        let sentence_start = (repetition * sentence.chars().count()) as u32;
+        assert_eq!(entities[0].word, "Amy");
+        assert_eq!(entities[0].offset.begin, sentence_start + 11);
+        assert_eq!(entities[1].word, "Paris");
+        assert_eq!(entities[1].offset.begin, sentence_start + 26);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bert_token_classification_hidden_states() -> anyhow::Result<()> {
+    //    Set-up model keeping the hidden states and attention weights
+    let model = TokenClassificationModel::new(TokenClassificationConfig {
+        output_hidden_states: true,
+        output_attentions: true,
+        ..Default::default()
+    })?;
+
+    //    Define input
+    let input = [
+        "My name is Amy. I live in Paris.",
+        "Paris is a city in France.",
+    ];
+
+    //    Run model
+    let output = model.predict_hidden_states(&input);
+
+    assert_eq!(output.len(), 2);
+    for hidden_states in output.iter() {
+        assert_eq!(hidden_states.all_hidden_states.len(), 24);
+        assert_eq!(hidden_states.all_attentions.len(), 24);
+        let hidden_state_size = hidden_states.all_hidden_states[0].size();
+        assert_eq!(hidden_state_size[0], 1);
+        assert_eq!(hidden_state_size[2], 1024);
+        let attention_size = hidden_states.all_attentions[0].size();
+        assert_eq!(attention_size[1], 16);
+        assert_eq!(attention_size[2], hidden_state_size[1]);
+    }
+
+    //    Predictions are unchanged
+    let default_model = TokenClassificationModel::new(Default::default())?;
+    assert_eq!(
+        model
+            .predict(&input, true, false)
+            .iter()
+            .map(|tokens| tokens.iter().map(|token| token.label.clone()).collect())
+            .collect::<Vec<Vec<String>>>(),
+        default_model
+            .predict(&input, true, false)
+            .iter()
+            .map(|tokens| tokens.iter().map(|token| token.label.clone()).collect())
+            .collect::<Vec<Vec<String>>>()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bert_token_classification_train_step() -> anyhow::Result<()> {
+    //    Set-up model and optimizer
+    let mut model = TokenClassificationModel::new(TokenClassificationConfig {
+        device: Device::Cpu,
+        ..Default::default()
+    })?;
+    let mut optimizer = nn::Adam::default().build(model.var_store(), 1e-4)?;
+
+    //    Define input: label all tokens as B-MISC, ignoring special tokens
+    let input_ids = Tensor::of_slice(&[101i64, 1422, 1271, 1110, 10280, 102]).unsqueeze(0);
+    let attention_mask = input_ids.ones_like();
+    let labels = Tensor::of_slice(&[-100i64, 1, 1, 1, 1, -100]).unsqueeze(0);
+
+    //    Run fine-tuning steps
+    let initial_loss = model.train_step(&mut optimizer, &input_ids, &attention_mask, &labels);
+    let mut loss = initial_loss;
+    for _ in 0..3 {
+        loss = model.train_step(&mut optimizer, &input_ids, &attention_mask, &labels);
+    }
+
+    assert!(initial_loss.is_finite());
+    assert!(loss < initial_loss);
+
+    Ok(())
+}
+
+#[test]
+fn bert_token_classification_mixed_lengths() -> anyhow::Result<()> {
+    //    Set-up model
+    let model = TokenClassificationModel::new(TokenClassificationConfig {
+        batch_size: 2,
+        ..Default::default()
+    })?;
+
+    //    Define input of heterogeneous lengths
+    let input = [
+        "In findings published Tuesday, a team of scientists from the University of Montreal confirmed the presence of water vapour in the atmosphere of K2-18b.",
+        "Amy lives in Paris.",
+        "The Montreal team, led by Björn Benneke, used data from the NASA's Hubble telescope.",
+        "Paris is a city in France.",
+    ];
+
+    //    Run model on the full batch and on each input separately
+    let output = model.predict(&input, true, false);
+
+    assert_eq!(output.len(), input.len());
+    for (sentence_tokens, sentence) in output.iter().zip(input.iter()) {
+        let expected = model.predict(&[*sentence], true, false).remove(0);
+        assert_eq!(sentence_tokens.len(), expected.len());
+        for (token, expected_token) in sentence_tokens.iter().zip(expected.iter()) {
+            assert_eq!(token.text, expected_token.text);
+            assert_eq!(token.label, expected_token.label);
+            assert!((token.score - expected_token.score).abs() < 1e-4);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bert_token_classification_without_special_tokens() -> anyhow::Result<()> {
+    //    Set-up model
+    let model = TokenClassificationModel::new(TokenClassificationConfig {
+        add_special_tokens: false,
+        ..Default::default()
+    })?;
+
+    //    Define input
+    let input = ["My name is Amy. I live in Paris."];
+
+    //    Run model, returning special tokens if any
+    let output = model.predict(&input, false, true);
+
+    assert_eq!(output[0].len(), 10);
+    assert_eq!(output[0][0].text, "My");
+    assert_eq!(output[0][0].index, 0);
+    assert_eq!(output[0].last().unwrap().text, ".");
+    assert!(output[0].iter().all(|token| token.mask != Mask::Special));
+
+    Ok(())
+}
+
+#[test]
+fn bert_token_classification_logits() -> anyhow::Result<()> {
+    //    Set-up model
+    let model = TokenClassificationModel::new(Default::default())?;
+
+    //    Define input
+    let input = ["My name is Amy. I live in Paris."];
+
+    //    Run model
+    let logits = model.predict_logits(&input, false);
+    let scores = model.predict_label_scores(&input, false);
+
+    assert_eq!(logits[0].len(), scores[0].len());
+    for (token_logits, token_scores) in logits[0].iter().zip(scores[0].iter()) {
+        assert_eq!(token_logits.token.label, token_scores.token.label);
+        let max_logit = token_logits
+            .label_logits
+            .iter()
+            .cloned()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let normalization = token_logits
+            .label_logits
+            .iter()
+            .map(|logit| (logit - max_logit).exp())
+            .sum::<f64>();
+        for (logit, score) in token_logits
+            .label_logits
+            .iter()
+            .zip(token_scores.label_scores.iter())
+        {
+            assert!(((logit - max_logit).exp() / normalization - score).abs() < 1e-4);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn bert_pre_trained_ner_async() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = Arc::new(NERModel::new(Default::default())?);
+
+    //    Define input
+    let input = vec![
+        "My name is Amy. I live in Paris.",
+        "Paris is a city in France.",
+    ];
+    let reference_output = ner_model.predict(&input);
+
+    //    Run model
+    let output = Arc::clone(&ner_model).predict_async(input).await?;
+
+    assert_eq!(output.len(), reference_output.len());
+    for (entities, reference_entities) in output.iter().zip(reference_output.iter()) {
+        assert_eq!(entities.len(), reference_entities.len());
+        for (entity, reference_entity) in entities.iter().zip(reference_entities.iter()) {
+            assert_eq!(entity.word, reference_entity.word);
+            assert_eq!(entity.label, reference_entity.label);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bert_ner_label_count_mismatch() -> anyhow::Result<()> {
+    //    Save the weights of a small model with 3 labels
+    let model_dir = tempfile::tempdir()?;
+    let mut config = BertConfig {
+        hidden_size: 8,
+        intermediate_size: 16,
+        max_position_embeddings: 32,
+        num_attention_heads: 2,
+        num_hidden_layers: 1,
+        vocab_size: 8,
+        id2label: Some(HashMap::from([
+            (0, "O".to_string()),
+            (1, "B-PER".to_string()),
+            (2, "I-PER".to_string()),
+        ])),
+        ..Default::default()
+    };
+    let var_store = nn::VarStore::new(Device::Cpu);
+    let _ = BertForTokenClassification::new(var_store.root(), &config)?;
+    var_store.save(model_dir.path().join("rust_model.ot"))?;
+
+    //    Declare only 2 labels in the configuration
+    config.id2label = Some(HashMap::from([
+        (0, "O".to_string()),
+        (1, "PER".to_string()),
+    ]));
+    fs::write(
+        model_dir.path().join("config.json"),
+        serde_json::to_string(&config)?,
+    )?;
+    fs::write(
+        model_dir.path().join("vocab.txt"),
+        "[PAD]\n[UNK]\n[CLS]\n[SEP]\n[MASK]\nmy\nname\namy\n",
+    )?;
+
+    let mut ner_config = TokenClassificationConfig::from_dir(model_dir.path())?;
+    ner_config.device = Device::Cpu;
+    let result = TokenClassificationModel::new(ner_config);
+
+    assert!(matches!(
+        result,
+        Err(RustBertError::InvalidConfigurationError(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn bert_ner_missing_weights() -> anyhow::Result<()> {
+    //    Save the weights of a small model with a single layer
+    let model_dir = tempfile::tempdir()?;
+    let mut config = BertConfig {
+        hidden_size: 8,
+        intermediate_size: 16,
+        max_position_embeddings: 32,
+        num_attention_heads: 2,
+        num_hidden_layers: 1,
+        vocab_size: 8,
+        id2label: Some(HashMap::from([
+            (0, "O".to_string()),
+            (1, "PER".to_string()),
+        ])),
+        ..Default::default()
+    };
+    let var_store = nn::VarStore::new(Device::Cpu);
+    let _ = BertForTokenClassification::new(var_store.root(), &config)?;
+    var_store.save(model_dir.path().join("rust_model.ot"))?;
+
+    //    Declare two layers in the configuration
+    config.num_hidden_layers = 2;
+    fs::write(
+        model_dir.path().join("config.json"),
+        serde_json::to_string(&config)?,
+    )?;
+    fs::write(
+        model_dir.path().join("vocab.txt"),
+        "[PAD]\n[UNK]\n[CLS]\n[SEP]\n[MASK]\nmy\nname\namy\n",
+    )?;
+
+    let mut ner_config = TokenClassificationConfig::from_dir(model_dir.path())?;
+    ner_config.device = Device::Cpu;
+    let result = TokenClassificationModel::new(ner_config);
+
+    match result {
+        Err(RustBertError::WeightMismatch {
+            missing,
+            unexpected,
+        }) => {
+            assert!(!missing.is_empty());
+            assert!(missing
+                .iter()
+                .all(|name| name.starts_with("bert.encoder.layer.1.")));
+            assert!(unexpected.is_empty());
+        }
+        _ => panic!("Expected a weight mismatch error"),
+    }
+
+    Ok(())
+}
+
 #[test]
 fn bert_pre_trained_ner_full_entities() -> anyhow::Result<()> {
     //    Set-up model
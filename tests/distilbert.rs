@@ -5,8 +5,11 @@ use rust_bert::distilbert::{
 };
 use rust_bert::pipelines::question_answering::{QaInput, QuestionAnsweringModel};
 use rust_bert::pipelines::sentiment::{SentimentModel, SentimentPolarity};
+use rust_bert::pipelines::sequence_classification::{
+    SequenceClassificationConfig, SequenceClassificationModel,
+};
 use rust_bert::resources::{RemoteResource, ResourceProvider};
-use rust_bert::Config;
+use rust_bert::{Config, RustBertError};
 use rust_tokenizers::tokenizer::{BertTokenizer, MultiThreadedTokenizer, TruncationStrategy};
 use rust_tokenizers::vocab::Vocab;
 use std::collections::HashMap;
@@ -26,7 +29,7 @@ fn distilbert_sentiment_classifier() -> anyhow::Result<()> {
         "If you like original gut wrenching laughter you will like this movie. If you are young or old then you will love this movie, hell even my mom liked it.",
     ];
 
-    let output = sentiment_classifier.predict(input);
+    let output = sentiment_classifier.predict(&input)?;
 
     assert_eq!(output.len(), 3usize);
     assert_eq!(output[0].polarity, SentimentPolarity::Positive);
@@ -39,6 +42,90 @@ fn distilbert_sentiment_classifier() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn distilbert_sentiment_classifier_padding() -> anyhow::Result<()> {
+    //    Set-up classifier
+    let sentiment_classifier = SentimentModel::new(Default::default())?;
+
+    //    Get sentiments for a short sentence, alone and batched with a longer sentence
+    let short_input = "This movie was great.";
+    let long_input = "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...";
+
+    let single_output = sentiment_classifier.predict(&[short_input])?;
+    let batched_output = sentiment_classifier.predict(&[short_input, long_input])?;
+
+    assert_eq!(single_output[0].polarity, batched_output[0].polarity);
+    assert!((single_output[0].score - batched_output[0].score).abs() < 1e-4);
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sentiment_classifier_empty_input() -> anyhow::Result<()> {
+    //    Set-up classifier
+    let sentiment_classifier = SentimentModel::new(Default::default())?;
+
+    //    Run model on an empty input
+    let input: [&str; 0] = [];
+    let output = sentiment_classifier.predict(&input)?;
+
+    assert!(output.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sequence_classifier_no_truncation() -> anyhow::Result<()> {
+    //    Set-up classifier with truncation disabled
+    let sequence_classifier = SequenceClassificationModel::new(SequenceClassificationConfig {
+        truncation_strategy: TruncationStrategy::DoNotTruncate,
+        ..Default::default()
+    })?;
+
+    //    Inputs within the maximum length of the model are processed
+    let output = sequence_classifier.predict_topk(&["This movie was great!"], 1)?;
+    assert_eq!(output[0][0].text, "POSITIVE");
+    let output = sequence_classifier.predict(&["This movie was great!"])?;
+    assert_eq!(output[0].text, "POSITIVE");
+
+    //    Over-length inputs are rejected
+    let long_input = "This movie was great! ".repeat(200);
+    assert!(matches!(
+        sequence_classifier.predict_topk(&[long_input.clone()], 1),
+        Err(RustBertError::ValueError(_))
+    ));
+    assert!(matches!(
+        sequence_classifier.predict(&[long_input]),
+        Err(RustBertError::ValueError(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sequence_classifier_pairs() -> anyhow::Result<()> {
+    //    Set-up classifier
+    let sequence_classifier = SequenceClassificationModel::new(Default::default())?;
+
+    //    Define input pairs
+    let input = [
+        ("This movie was great!", "I would watch it again."),
+        ("This movie was terrible.", "I want my money back."),
+    ];
+
+    //    Run model
+    let output = sequence_classifier.predict_pairs(&input)?;
+
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[0].sentence, 0);
+    assert_eq!(output[1].sentence, 1);
+    assert_eq!(output[0].text, "POSITIVE");
+    assert_eq!(output[1].text, "NEGATIVE");
+    assert!(sequence_classifier.predict_pairs(&[])?.is_empty());
+
+    Ok(())
+}
+
 #[test]
 fn distilbert_masked_lm() -> anyhow::Result<()> {
     //    Resources paths